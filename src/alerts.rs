@@ -0,0 +1,501 @@
+use async_trait::async_trait;
+use chrono::{SecondsFormat, Utc};
+use hmac::{Hmac, Mac};
+use log::{debug, info, warn};
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use std::collections::{BTreeMap, HashMap};
+use std::error::Error;
+use std::fs::{self, File, OpenOptions};
+use std::io::Write;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::io::AsyncWriteExt;
+use tokio::net::{TcpStream, UdpSocket};
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+use uuid::Uuid;
+
+use crate::config::AlertsConfig;
+use crate::findings::Direction;
+use crate::guard::Verdict;
+use crate::logging::{self, ContentMode};
+
+pub type SinkError = Box<dyn Error + Send + Sync>;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Low,
+    Medium,
+    High,
+    Critical,
+}
+
+impl Severity {
+    pub fn parse(value: &str) -> Option<Severity> {
+        match value {
+            "low" => Some(Severity::Low),
+            "medium" => Some(Severity::Medium),
+            "high" => Some(Severity::High),
+            "critical" => Some(Severity::Critical),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Severity::Low => "low",
+            Severity::Medium => "medium",
+            Severity::High => "high",
+            Severity::Critical => "critical",
+        }
+    }
+
+    /// CEF severity, 0 to 10.
+    fn cef(&self) -> u8 {
+        match self {
+            Severity::Low => 3,
+            Severity::Medium => 5,
+            Severity::High => 8,
+            Severity::Critical => 10,
+        }
+    }
+
+    /// Syslog severity: notice, warning, error or critical.
+    fn syslog(&self) -> u8 {
+        match self {
+            Severity::Low => 5,
+            Severity::Medium => 4,
+            Severity::High => 3,
+            Severity::Critical => 2,
+        }
+    }
+}
+
+/// One rule that blocked one message.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Alert {
+    pub id: String,
+    pub time: String,
+    pub rule: String,
+    pub severity: Severity,
+    pub direction: Direction,
+    pub app: Option<String>,
+    pub request_id: String,
+    pub description: String,
+    /// How many findings the rule produced for the message.
+    pub matches: usize,
+    /// The message as `logging.content` allows it to be shown.
+    pub content: String,
+    /// Hash of the message, identical for identical messages.
+    pub fingerprint: String,
+}
+
+/// Somewhere alerts are delivered. Sinks run one at a time on the alert
+/// task, so they can hold connections and files without locking.
+#[async_trait]
+pub trait AlertSink: Send {
+    fn name(&self) -> &str;
+
+    async fn send(&mut self, alert: &Alert) -> Result<(), SinkError>;
+}
+
+/// Decides which alerts go out: drops those below the minimum severity,
+/// repeats of the same rule on the same message within the dedup window, and
+/// anything over a rule's per-minute limit.
+pub struct AlertGate {
+    min_severity: Severity,
+    dedup_window: Duration,
+    /// Alerts per rule per minute; 0 is unlimited.
+    rate_limit: u32,
+    seen: HashMap<(String, String), Instant>,
+    windows: HashMap<String, (Instant, u32)>,
+}
+
+impl AlertGate {
+    pub fn new(min_severity: Severity, dedup_window: Duration, rate_limit: u32) -> Self {
+        AlertGate {
+            min_severity,
+            dedup_window,
+            rate_limit,
+            seen: HashMap::new(),
+            windows: HashMap::new(),
+        }
+    }
+
+    pub fn admit(&mut self, alert: &Alert, now: Instant) -> bool {
+        if alert.severity < self.min_severity {
+            return false;
+        }
+
+        let key = (alert.rule.clone(), alert.fingerprint.clone());
+        if let Some(last) = self.seen.get(&key) {
+            if now.duration_since(*last) < self.dedup_window {
+                return false;
+            }
+        }
+
+        if self.rate_limit > 0 {
+            let window = self.windows.entry(alert.rule.clone()).or_insert((now, 0));
+            if now.duration_since(window.0) >= Duration::from_secs(60) {
+                *window = (now, 0);
+            }
+            if window.1 >= self.rate_limit {
+                return false;
+            }
+            window.1 += 1;
+        }
+
+        if self.seen.len() >= 10_000 {
+            let dedup_window = self.dedup_window;
+            self.seen.retain(|_, last| now.duration_since(*last) < dedup_window);
+        }
+        self.seen.insert(key, now);
+        true
+    }
+}
+
+/// `sha256=<hex>` HMAC of a webhook body, as sent in `X-Signature-256`.
+pub fn sign(secret: &[u8], body: &[u8]) -> String {
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret).expect("HMAC takes keys of any length");
+    mac.update(body);
+    let digest: String = mac.finalize().into_bytes().iter().map(|b| format!("{:02x}", b)).collect();
+    format!("sha256={}", digest)
+}
+
+/// POSTs each alert as JSON, signed with HMAC-SHA256 when a secret is set.
+pub struct WebhookSink {
+    client: reqwest::Client,
+    url: String,
+    secret: Option<Vec<u8>>,
+}
+
+impl WebhookSink {
+    pub fn new(url: &str, secret: Option<&str>, timeout: Duration) -> Result<Self, SinkError> {
+        Ok(WebhookSink {
+            client: reqwest::Client::builder().timeout(timeout).build()?,
+            url: url.to_string(),
+            secret: secret.map(|s| s.as_bytes().to_vec()),
+        })
+    }
+}
+
+#[async_trait]
+impl AlertSink for WebhookSink {
+    fn name(&self) -> &str {
+        "webhook"
+    }
+
+    async fn send(&mut self, alert: &Alert) -> Result<(), SinkError> {
+        let body = serde_json::to_vec(alert)?;
+        let mut request = self.client.post(&self.url).header("Content-Type", "application/json");
+        if let Some(secret) = &self.secret {
+            request = request.header("X-Signature-256", sign(secret, &body));
+        }
+        request.body(body).send().await?.error_for_status()?;
+        Ok(())
+    }
+}
+
+fn cef_header(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('|', "\\|").replace(['\r', '\n'], " ")
+}
+
+fn cef_extension(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('=', "\\=").replace('\r', "\\r").replace('\n', "\\n")
+}
+
+/// ArcSight Common Event Format.
+pub fn to_cef(alert: &Alert) -> String {
+    format!(
+        "CEF:0|LLM Validator|llm_validator|{}|{}|{}|{}|rt={} externalId={} cs1Label=app cs1={} cs2Label=direction cs2={} cnt={} msg={} fileHash={}",
+        env!("CARGO_PKG_VERSION"),
+        cef_header(&alert.rule),
+        cef_header(&alert.description),
+        alert.severity.cef(),
+        cef_extension(&alert.time),
+        cef_extension(&alert.request_id),
+        cef_extension(alert.app.as_deref().unwrap_or("")),
+        alert.direction.as_str(),
+        alert.matches,
+        cef_extension(&alert.content),
+        cef_extension(&alert.fingerprint),
+    )
+}
+
+fn leef_value(value: &str) -> String {
+    value.replace(['\t', '\r', '\n'], " ")
+}
+
+/// IBM QRadar Log Event Extended Format, tab separated.
+pub fn to_leef(alert: &Alert) -> String {
+    let severity = alert.severity.cef().to_string();
+    let attributes = [
+        ("devTime", alert.time.as_str()),
+        ("sev", severity.as_str()),
+        ("cat", alert.rule.as_str()),
+        ("requestId", alert.request_id.as_str()),
+        ("app", alert.app.as_deref().unwrap_or("")),
+        ("direction", alert.direction.as_str()),
+        ("msg", alert.description.as_str()),
+        ("content", alert.content.as_str()),
+    ]
+    .iter()
+    .map(|(key, value)| format!("{}={}", key, leef_value(value)))
+    .collect::<Vec<_>>()
+    .join("\t");
+    format!("LEEF:2.0|LLM Validator|llm_validator|{}|{}|{}", env!("CARGO_PKG_VERSION"), leef_value(&alert.rule).replace('|', " "), attributes)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SyslogFormat {
+    Cef,
+    Leef,
+}
+
+enum SyslogTransport {
+    Udp(UdpSocket),
+    /// Connected on first use and again after a failed write.
+    Tcp(Option<TcpStream>),
+}
+
+/// Sends RFC 5424 syslog messages with a CEF or LEEF body.
+pub struct SyslogSink {
+    address: String,
+    format: SyslogFormat,
+    transport: SyslogTransport,
+    hostname: String,
+}
+
+impl SyslogSink {
+    pub async fn new(address: &str, tcp: bool, format: SyslogFormat) -> Result<Self, SinkError> {
+        let transport = if tcp {
+            SyslogTransport::Tcp(None)
+        } else {
+            let socket = UdpSocket::bind("0.0.0.0:0").await?;
+            socket.connect(address).await?;
+            SyslogTransport::Udp(socket)
+        };
+        Ok(SyslogSink {
+            address: address.to_string(),
+            format,
+            transport,
+            hostname: std::env::var("HOSTNAME").unwrap_or_else(|_| "-".to_string()),
+        })
+    }
+
+    /// Facility 13 (log audit) at the alert's severity.
+    fn line(&self, alert: &Alert) -> String {
+        let body = match self.format {
+            SyslogFormat::Cef => to_cef(alert),
+            SyslogFormat::Leef => to_leef(alert),
+        };
+        format!("<{}>1 {} {} llm_validator - - - {}", 13 * 8 + alert.severity.syslog(), alert.time, self.hostname, body)
+    }
+}
+
+#[async_trait]
+impl AlertSink for SyslogSink {
+    fn name(&self) -> &str {
+        "syslog"
+    }
+
+    async fn send(&mut self, alert: &Alert) -> Result<(), SinkError> {
+        let line = self.line(alert);
+        match &mut self.transport {
+            SyslogTransport::Udp(socket) => {
+                socket.send(line.as_bytes()).await?;
+            }
+            SyslogTransport::Tcp(stream) => {
+                if stream.is_none() {
+                    *stream = Some(TcpStream::connect(&self.address).await?);
+                }
+                let connection = stream.as_mut().expect("connected above");
+                if let Err(e) = connection.write_all(format!("{}\n", line).as_bytes()).await {
+                    *stream = None;
+                    return Err(e.into());
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Appends alerts as NDJSON, rotating to `path.1`, `path.2`, ... once the
+/// file would grow past `max_bytes`. Keeps `keep` rotated files.
+pub struct FileSink {
+    path: PathBuf,
+    max_bytes: u64,
+    keep: usize,
+    file: Option<File>,
+    size: u64,
+}
+
+impl FileSink {
+    pub fn new(path: impl Into<PathBuf>, max_bytes: u64, keep: usize) -> Self {
+        FileSink {
+            path: path.into(),
+            max_bytes,
+            keep,
+            file: None,
+            size: 0,
+        }
+    }
+
+    fn rotated(&self, index: usize) -> PathBuf {
+        let mut name = self.path.clone().into_os_string();
+        name.push(format!(".{}", index));
+        PathBuf::from(name)
+    }
+
+    fn rotate(&mut self) -> std::io::Result<()> {
+        self.file = None;
+        if self.keep == 0 {
+            return fs::remove_file(&self.path);
+        }
+        let _ = fs::remove_file(self.rotated(self.keep));
+        for index in (1..self.keep).rev() {
+            let from = self.rotated(index);
+            if from.exists() {
+                fs::rename(&from, self.rotated(index + 1))?;
+            }
+        }
+        fs::rename(&self.path, self.rotated(1))
+    }
+
+    fn write_line(&mut self, line: &str) -> std::io::Result<()> {
+        if self.file.is_none() {
+            let file = OpenOptions::new().create(true).append(true).open(&self.path)?;
+            self.size = file.metadata()?.len();
+            self.file = Some(file);
+        }
+        if self.size > 0 && self.size + line.len() as u64 > self.max_bytes {
+            self.rotate()?;
+            return self.write_line(line);
+        }
+        self.file.as_mut().expect("opened above").write_all(line.as_bytes())?;
+        self.size += line.len() as u64;
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl AlertSink for FileSink {
+    fn name(&self) -> &str {
+        "file"
+    }
+
+    async fn send(&mut self, alert: &Alert) -> Result<(), SinkError> {
+        let line = serde_json::to_string(alert)? + "\n";
+        Ok(self.write_line(&line)?)
+    }
+}
+
+/// Handle used on the validation path to raise alerts. Raising never waits
+/// on a sink: when the queue is full the alerts are dropped and counted.
+#[derive(Clone)]
+pub struct AlertHandle {
+    sender: mpsc::Sender<Alert>,
+    severities: Arc<BTreeMap<String, Severity>>,
+    default_severity: Severity,
+    dropped: Arc<AtomicU64>,
+}
+
+impl AlertHandle {
+    fn severity(&self, rule: &str) -> Severity {
+        self.severities.get(rule).copied().unwrap_or(self.default_severity)
+    }
+
+    /// Raises one alert per rule that blocked the message. Does nothing for
+    /// messages that were let through.
+    pub fn raise(&self, verdict: &Verdict, text: &str, request_id: &str, app: Option<&str>) {
+        if !verdict.blocked {
+            return;
+        }
+        let time = Utc::now().to_rfc3339_opts(SecondsFormat::Millis, true);
+        let content = logging::content(text);
+        let fingerprint = ContentMode::Hash.apply(text);
+
+        let mut by_rule: BTreeMap<&str, (usize, &str)> = BTreeMap::new();
+        for finding in &verdict.findings {
+            by_rule.entry(finding.rule.as_str()).or_insert((0, finding.description.as_str())).0 += 1;
+        }
+        for (rule, (matches, description)) in by_rule {
+            let alert = Alert {
+                id: Uuid::new_v4().to_string(),
+                time: time.clone(),
+                rule: rule.to_string(),
+                severity: self.severity(rule),
+                direction: verdict.direction,
+                app: app.map(String::from),
+                request_id: request_id.to_string(),
+                description: description.to_string(),
+                matches,
+                content: content.clone(),
+                fingerprint: fingerprint.clone(),
+            };
+            if self.sender.try_send(alert).is_err() {
+                let dropped = self.dropped.fetch_add(1, Ordering::Relaxed) + 1;
+                warn!("Alert queue full or closed, {} alerts dropped so far", dropped);
+            }
+        }
+    }
+}
+
+/// Starts the alert task. It finishes once every `AlertHandle` clone has
+/// been dropped and the queued alerts are delivered.
+pub fn spawn(sinks: Vec<Box<dyn AlertSink>>, gate: AlertGate, config: &AlertsConfig) -> (AlertHandle, JoinHandle<()>) {
+    let (sender, receiver) = mpsc::channel(config.capacity);
+    let severities = config.severities.iter().filter_map(|(rule, s)| Severity::parse(s).map(|s| (rule.clone(), s))).collect();
+    let handle = AlertHandle {
+        sender,
+        severities: Arc::new(severities),
+        default_severity: Severity::parse(&config.default_severity).unwrap_or(Severity::Medium),
+        dropped: Arc::new(AtomicU64::new(0)),
+    };
+    (handle, tokio::spawn(run_alerts(receiver, gate, sinks)))
+}
+
+async fn run_alerts(mut receiver: mpsc::Receiver<Alert>, mut gate: AlertGate, mut sinks: Vec<Box<dyn AlertSink>>) {
+    while let Some(alert) = receiver.recv().await {
+        if !gate.admit(&alert, Instant::now()) {
+            debug!("Suppressed {} alert for {}", alert.rule, alert.request_id);
+            continue;
+        }
+        for sink in sinks.iter_mut() {
+            if let Err(e) = sink.send(&alert).await {
+                warn!(sink = sink.name(), rule = alert.rule.as_str(); "Failed to deliver alert: {}", e);
+            }
+        }
+    }
+}
+
+/// The sinks configured under `[sinks.alerts]`, started. `None` when no sink
+/// is configured.
+pub async fn start(config: &AlertsConfig) -> Result<Option<(AlertHandle, JoinHandle<()>)>, SinkError> {
+    let mut sinks: Vec<Box<dyn AlertSink>> = Vec::new();
+    if let Some(webhook) = &config.webhook {
+        sinks.push(Box::new(WebhookSink::new(&webhook.url, webhook.secret.as_deref(), Duration::from_secs(webhook.timeout_secs))?));
+    }
+    if let Some(syslog) = &config.syslog {
+        let format = if syslog.format == "leef" { SyslogFormat::Leef } else { SyslogFormat::Cef };
+        sinks.push(Box::new(SyslogSink::new(&syslog.address, syslog.protocol == "tcp", format).await?));
+    }
+    if let Some(file) = &config.file {
+        sinks.push(Box::new(FileSink::new(&file.path, file.max_bytes, file.keep)));
+    }
+    if sinks.is_empty() {
+        return Ok(None);
+    }
+
+    info!("Sending alerts to {}", sinks.iter().map(|s| s.name()).collect::<Vec<_>>().join(", "));
+    let gate = AlertGate::new(
+        Severity::parse(&config.min_severity).unwrap_or(Severity::Low),
+        Duration::from_secs(config.dedup_window_secs),
+        config.rate_limit_per_minute,
+    );
+    Ok(Some(spawn(sinks, gate, config)))
+}
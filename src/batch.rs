@@ -0,0 +1,232 @@
+use clap::Args;
+use log::warn;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+use std::sync::Arc;
+use std::time::Instant;
+
+use crate::cli::DetectionArgs;
+use crate::config::Config;
+use crate::db::audit::{AuditWriter, ValidationRecord};
+use crate::db::store::PatternStore;
+use crate::db::Database;
+use crate::findings::{Direction, Finding};
+use crate::guard::Guard;
+use crate::metrics;
+
+type RecordError = Box<dyn Error + Send + Sync>;
+
+/// One exchange from a conversation log.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ConversationRecord {
+    #[serde(default)]
+    pub id: Option<String>,
+    pub prompt: String,
+    #[serde(default)]
+    pub response: Option<String>,
+    #[serde(default)]
+    pub user: Option<String>,
+    #[serde(default)]
+    pub app: Option<String>,
+}
+
+/// Result of validating one record.
+#[derive(Debug, Clone, Serialize)]
+pub struct RecordVerdict {
+    /// Line of the file the record starts on.
+    pub line: usize,
+    pub id: Option<String>,
+    pub user: Option<String>,
+    pub app: Option<String>,
+    pub blocked: bool,
+    pub input_findings: Vec<Finding>,
+    pub output_findings: Vec<Finding>,
+}
+
+#[derive(Debug, Default, Serialize)]
+pub struct BatchSummary {
+    pub records: usize,
+    pub malformed: usize,
+    pub blocked: usize,
+    pub flagged_inputs: usize,
+    pub flagged_outputs: usize,
+    pub by_rule: BTreeMap<String, usize>,
+    /// Blocked records per app.
+    pub blocked_by_app: BTreeMap<String, usize>,
+}
+
+impl BatchSummary {
+    fn add(&mut self, verdict: &RecordVerdict) {
+        self.records += 1;
+        self.flagged_inputs += !verdict.input_findings.is_empty() as usize;
+        self.flagged_outputs += !verdict.output_findings.is_empty() as usize;
+        for finding in verdict.input_findings.iter().chain(&verdict.output_findings) {
+            *self.by_rule.entry(finding.rule.clone()).or_default() += 1;
+        }
+        if verdict.blocked {
+            self.blocked += 1;
+            let app = verdict.app.clone().unwrap_or_else(|| "(none)".to_string());
+            *self.blocked_by_app.entry(app).or_default() += 1;
+        }
+    }
+}
+
+/// Streams records from a `.jsonl` or `.csv` log without loading the whole
+/// file, each with the line it starts on. CSV files use the header
+/// `id,prompt,response,user,app`.
+pub fn read_records(path: &str) -> Result<Box<dyn Iterator<Item = (usize, Result<ConversationRecord, RecordError>)>>, Box<dyn Error>> {
+    if path.ends_with(".csv") {
+        let mut reader = csv::Reader::from_path(path)?;
+        let headers = reader.headers()?.clone();
+        Ok(Box::new(reader.into_records().enumerate().map(move |(index, row)| {
+            // The header is line 1; rows only lack a position on I/O errors.
+            let fallback = index + 2;
+            match row {
+                Ok(row) => {
+                    let line = row.position().map_or(fallback, |p| p.line() as usize);
+                    (line, row.deserialize(Some(&headers)).map_err(RecordError::from))
+                }
+                Err(e) => (e.position().map_or(fallback, |p| p.line() as usize), Err(e.into())),
+            }
+        })))
+    } else if path.ends_with(".jsonl") || path.ends_with(".ndjson") {
+        let reader = BufReader::new(File::open(path)?);
+        Ok(Box::new(
+            reader
+                .lines()
+                .enumerate()
+                .filter(|(_, l)| !matches!(l, Ok(l) if l.trim().is_empty()))
+                .map(|(index, line)| {
+                    let record = line.map_err(RecordError::from).and_then(|line| Ok(serde_json::from_str(&line)?));
+                    (index + 1, record)
+                }),
+        ))
+    } else {
+        Err(format!("{}: expected a .jsonl or .csv conversation log", path).into())
+    }
+}
+
+/// `request_id` tags the record's audit runs; the record's id, or where it
+/// is in the log.
+async fn validate_record(line: usize, conversation: ConversationRecord, guard: Arc<Guard>, audit: Option<AuditWriter>, request_id: String) -> RecordVerdict {
+    let app = conversation.app.as_deref();
+    let started = Instant::now();
+    let input = guard.check_message(&conversation.prompt, Direction::Input, app).await;
+    metrics::record_verdict("scan", Direction::Input, input.blocked, started.elapsed());
+    if let Some(audit) = &audit {
+        audit.record(ValidationRecord::new(Direction::Input, input.findings.clone(), started.elapsed()).for_request(&request_id, app));
+    }
+    let mut blocked = input.blocked;
+    let output_findings = match &conversation.response {
+        Some(response) => {
+            let started = Instant::now();
+            let output = guard.check_message(response, Direction::Output, app).await;
+            metrics::record_verdict("scan", Direction::Output, output.blocked, started.elapsed());
+            if let Some(audit) = &audit {
+                audit.record(ValidationRecord::new(Direction::Output, output.findings.clone(), started.elapsed()).for_request(&request_id, app));
+            }
+            blocked |= output.blocked;
+            output.findings
+        }
+        None => Vec::new(),
+    };
+    RecordVerdict {
+        line,
+        id: conversation.id,
+        user: conversation.user,
+        app: conversation.app,
+        blocked,
+        input_findings: input.findings,
+        output_findings,
+    }
+}
+
+/// Validates every record, `chunk_size` at a time concurrently, and hands the
+/// verdicts to `on_verdict` in file order. At most one chunk is held in
+/// memory, so multi-gigabyte logs run in constant space. Each record is
+/// checked under the limits of its app, and recorded in the audit trail when
+/// `audit` is given.
+pub async fn validate_log<F>(path: &str, guard: &Arc<Guard>, audit: Option<&AuditWriter>, chunk_size: usize, mut on_verdict: F) -> Result<BatchSummary, Box<dyn Error>>
+where
+    F: FnMut(&RecordVerdict),
+{
+    let mut records = read_records(path)?;
+    let mut summary = BatchSummary::default();
+
+    loop {
+        let mut chunk = Vec::with_capacity(chunk_size);
+        let mut read = 0;
+        for (line, record) in records.by_ref().take(chunk_size) {
+            read += 1;
+            match record {
+                Ok(record) => chunk.push((line, record)),
+                Err(e) => {
+                    warn!("Skipping malformed record at {}:{}: {}", path, line, e);
+                    summary.malformed += 1;
+                }
+            }
+        }
+        if read == 0 {
+            break;
+        }
+
+        let running: Vec<_> = chunk
+            .into_iter()
+            .map(|(line, record)| {
+                let request_id = record.id.clone().unwrap_or_else(|| format!("{}:{}", path, line));
+                tokio::spawn(validate_record(line, record, guard.clone(), audit.cloned(), request_id))
+            })
+            .collect();
+        for task in running {
+            let verdict = task.await?;
+            summary.add(&verdict);
+            on_verdict(&verdict);
+        }
+    }
+
+    Ok(summary)
+}
+
+#[derive(Debug, Args)]
+pub struct ScanArgs {
+    /// A `.jsonl` or `.csv` conversation log.
+    pub log: String,
+
+    /// Records validated in parallel at a time; bounds memory use.
+    #[arg(long)]
+    pub chunk: Option<usize>,
+
+    #[command(flatten)]
+    pub detection: DetectionArgs,
+}
+
+/// Handles `scan <log.jsonl|log.csv> [--detectors a,b] [--policy FILE] [--chunk N]`.
+///
+/// Prints one JSON verdict per record on stdout and a summary on stderr, so
+/// the output can be piped straight into other tools. `db` is needed for the
+/// audit trail. Returns whether any record was blocked so the caller can
+/// exit with `EXIT_BLOCKED`.
+pub async fn run_scan_command(args: &ScanArgs, config: &Config, db: Option<&Database>, store: Arc<dyn PatternStore>) -> Result<bool, Box<dyn Error>> {
+    let guard = Arc::new(config.detection.guard(store, config.workers.compute_pool()?).await?);
+    let audit = AuditWriter::from_config(&config.sinks.audit, db);
+    let summary = validate_log(&args.log, &guard, audit.as_ref().map(|(writer, _)| writer), config.workers.scan_chunk, |verdict| {
+        println!("{}", serde_json::to_string(verdict).unwrap_or_default());
+    })
+    .await?;
+    if let Some((writer, task)) = audit {
+        writer.finish(task).await;
+    }
+
+    eprintln!(
+        "Validated {} records ({} malformed): {} blocked, {} flagged inputs, {} flagged outputs",
+        summary.records, summary.malformed, summary.blocked, summary.flagged_inputs, summary.flagged_outputs
+    );
+    for (rule, count) in &summary.by_rule {
+        eprintln!("  {:<32} {}", rule, count);
+    }
+
+    Ok(summary.blocked > 0)
+}
@@ -0,0 +1,252 @@
+use clap::{Args, Parser, Subcommand};
+use std::error::Error;
+use std::path::PathBuf;
+
+use crate::batch::ScanArgs;
+use crate::config::{split_list, Config, DetectionConfig};
+use crate::datasets::DatasetsCommand;
+use crate::db::migrations::{self, MigrateCommand};
+use crate::db::pattern_history::{self, PatternsCommand};
+use crate::db::reputation::{self, ReputationCommand};
+use crate::db::{store, Database};
+use crate::documents::DocumentsArgs;
+use crate::eval::EvalArgs;
+use crate::mutation::RedteamArgs;
+use crate::report::CheckArgs;
+use crate::serve::ServeArgs;
+use crate::stream::StreamArgs;
+use crate::tune::TuneArgs;
+use crate::{batch, datasets, documents, eval, logging, mutation, report, serve, stream, telemetry, tune};
+
+/// Validates prompts going into an LLM and responses coming back out.
+///
+/// Flags that mirror a configuration setting override the config file and the
+/// environment.
+#[derive(Debug, Parser)]
+#[command(name = "llm_validator", version)]
+pub struct Cli {
+    /// TOML config file; defaults to `llm_validator.toml` when it exists.
+    #[arg(long, global = true, env = "LLM_VALIDATOR_CONFIG")]
+    pub config: Option<PathBuf>,
+
+    /// off, error, warn, info, debug or trace.
+    #[arg(long, global = true)]
+    pub log_level: Option<String>,
+
+    /// text or json.
+    #[arg(long, global = true)]
+    pub log_format: Option<String>,
+
+    #[command(subcommand)]
+    pub command: Command,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum Command {
+    /// Validate a prompt file and a response file and report the findings.
+    Check(CheckArgs),
+    /// Validate a JSONL or CSV conversation log.
+    Scan(ScanArgs),
+    /// Check retrieved documents (web pages, markdown, PDF text) for indirect prompt injection.
+    Documents(DocumentsArgs),
+    /// Accept messages over TCP and validate them as they arrive.
+    Serve(ServeArgs),
+    /// Validate newline-delimited messages from stdin, a socket, a file or Kafka.
+    Stream(StreamArgs),
+    /// Score a detector configuration against a labeled dataset.
+    Eval(EvalArgs),
+    /// Sweep detector thresholds over a labeled dataset.
+    Tune(TuneArgs),
+    /// Mutate seed attacks and report which variants evade detection.
+    Redteam(RedteamArgs),
+    /// Import and inspect labeled datasets.
+    #[command(subcommand)]
+    Datasets(DatasetsCommand),
+    /// Inspect and roll back pattern history.
+    #[command(subcommand)]
+    Patterns(PatternsCommand),
+    /// Inspect and reset saved client reputations.
+    #[command(subcommand)]
+    Reputation(ReputationCommand),
+    /// Apply or revert database migrations.
+    #[command(subcommand)]
+    Migrate(MigrateCommand),
+}
+
+/// Detector selection and policy, shared by every command that validates text.
+#[derive(Debug, Clone, Args)]
+pub struct DetectionArgs {
+    /// Comma separated detectors to run.
+    #[arg(long)]
+    pub detectors: Option<String>,
+
+    /// Policy file with per-detector thresholds.
+    #[arg(long)]
+    pub policy: Option<String>,
+}
+
+impl DetectionArgs {
+    fn apply(&self, config: &mut DetectionConfig) {
+        if let Some(list) = &self.detectors {
+            config.detectors = split_list(list);
+        }
+        if let Some(path) = &self.policy {
+            config.policy = Some(path.clone());
+        }
+    }
+}
+
+impl Cli {
+    /// The command line layer: copies every flag that mirrors a setting into
+    /// the configuration. `Config::load` runs it before validating.
+    pub fn apply(&self, config: &mut Config) {
+        if let Some(level) = &self.log_level {
+            config.log_level = level.clone();
+        }
+        if let Some(format) = &self.log_format {
+            config.logging.format = format.clone();
+        }
+        match &self.command {
+            Command::Check(args) => args.detection.apply(&mut config.detection),
+            Command::Documents(args) => args.detection.apply(&mut config.detection),
+            Command::Scan(args) => {
+                args.detection.apply(&mut config.detection);
+                if let Some(chunk) = args.chunk {
+                    config.workers.scan_chunk = chunk;
+                }
+            }
+            Command::Serve(args) => {
+                args.detection.apply(&mut config.detection);
+                if let Some(listen) = &args.listen {
+                    config.server.listen = listen.clone();
+                }
+                if let Some(listen) = &args.metrics_listen {
+                    config.server.metrics_listen = Some(listen.clone());
+                }
+                if let Some(validators) = args.validators {
+                    config.workers.validators = validators;
+                }
+                if let Some(depth) = args.queue_depth {
+                    config.workers.queue_depth = depth;
+                }
+            }
+            Command::Stream(args) => {
+                args.detection.apply(&mut config.detection);
+                if let Some(source) = &args.source {
+                    config.stream.source = source.clone();
+                }
+                if let Some(sink) = &args.sink {
+                    config.stream.sink = sink.clone();
+                }
+            }
+            Command::Redteam(args) => args.detection.apply(&mut config.detection),
+            Command::Eval(args) => args.detection.apply(&mut config.detection),
+            Command::Tune(args) => {
+                if let Some(list) = &args.detectors {
+                    config.detection.detectors = split_list(list);
+                }
+            }
+            Command::Datasets(_) | Command::Patterns(_) | Command::Reputation(_) | Command::Migrate(_) => {}
+        }
+    }
+}
+
+/// Whether conversations or reputations are kept in Postgres.
+fn state_in_database(config: &Config) -> bool {
+    (config.conversations.enabled && config.conversations.store == "database") || (config.reputation.enabled && config.reputation.store == "database")
+}
+
+async fn connect(config: &Config) -> Result<Database, Box<dyn Error>> {
+    let db = Database::from_config(&config.database).await?;
+    db.check_schema().await?;
+    Ok(db)
+}
+
+/// The database the audit trail goes to, when `sinks.audit` is enabled.
+async fn audit_database(config: &Config) -> Result<Option<Database>, Box<dyn Error>> {
+    match config.sinks.audit.enabled {
+        true => Ok(Some(connect(config).await?)),
+        false => Ok(None),
+    }
+}
+
+/// Loads the configuration, runs the command and returns the process exit
+/// code.
+pub async fn run(cli: &Cli) -> Result<i32, Box<dyn Error>> {
+    let config = Config::load(cli.config.as_deref(), |config| cli.apply(config))?;
+
+    logging::init(config.log_level_filter(), &config.logging);
+    let _telemetry = telemetry::init(&config.tracing)?;
+
+    match &cli.command {
+        Command::Check(args) => {
+            let db = audit_database(&config).await?;
+            let store = store::open_store(&config, db.as_ref()).await?;
+            if report::run_check_command(args, &config, db.as_ref(), store).await? {
+                return Ok(report::EXIT_BLOCKED);
+            }
+        }
+        Command::Scan(args) => {
+            let db = audit_database(&config).await?;
+            let store = store::open_store(&config, db.as_ref()).await?;
+            if batch::run_scan_command(args, &config, db.as_ref(), store).await? {
+                return Ok(report::EXIT_BLOCKED);
+            }
+        }
+        Command::Documents(args) => {
+            let db = audit_database(&config).await?;
+            let store = store::open_store(&config, db.as_ref()).await?;
+            if documents::run_documents_command(args, &config, db.as_ref(), store).await? {
+                return Ok(report::EXIT_BLOCKED);
+            }
+        }
+        Command::Serve(_) => {
+            let db = match config.sinks.audit.enabled || state_in_database(&config) {
+                true => Some(connect(&config).await?),
+                false => None,
+            };
+            let store = store::open_store(&config, db.as_ref()).await?;
+            serve::run_serve_command(&config, db.as_ref(), store).await?;
+        }
+        Command::Stream(args) => {
+            let db = match config.sinks.audit.enabled || state_in_database(&config) {
+                true => Some(connect(&config).await?),
+                false => None,
+            };
+            let store = store::open_store(&config, db.as_ref()).await?;
+            stream::run_stream_command(args, &config, db.as_ref(), store).await?;
+        }
+        Command::Eval(args) => {
+            let db = connect(&config).await?;
+            let store = store::open_store(&config, Some(&db)).await?;
+            eval::run_eval_command(args, &config, &db, store.as_ref()).await?;
+        }
+        Command::Tune(args) => {
+            let db = connect(&config).await?;
+            let store = store::open_store(&config, Some(&db)).await?;
+            tune::run_tune_command(args, &config, &db, store.as_ref()).await?;
+        }
+        Command::Redteam(args) => {
+            let store = store::open_store(&config, None).await?;
+            mutation::run_redteam_command(args, &config, store).await?;
+        }
+        Command::Datasets(command) => {
+            let db = connect(&config).await?;
+            datasets::run_datasets_command(command, &config.datasets, &db).await?;
+        }
+        Command::Patterns(command) => {
+            let db = connect(&config).await?;
+            pattern_history::run_patterns_command(command, &db).await?;
+        }
+        Command::Reputation(command) => {
+            let db = connect(&config).await?;
+            reputation::run_reputation_command(command, &db).await?;
+        }
+        Command::Migrate(command) => {
+            let db = Database::from_config(&config.database).await?;
+            migrations::run_migrate_command(command, &db).await?;
+        }
+    }
+
+    Ok(0)
+}
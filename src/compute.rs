@@ -0,0 +1,80 @@
+use rayon::prelude::*;
+use rayon::{ThreadPool, ThreadPoolBuildError, ThreadPoolBuilder};
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::{Arc, OnceLock};
+use std::thread;
+use tokio::sync::oneshot;
+
+static SHARED: OnceLock<ComputePool> = OnceLock::new();
+
+/// A dedicated rayon pool for CPU-bound work (regex matching, tokenizing,
+/// model inference) started from async code.
+///
+/// `run` and `map` hand the work over and await a reply channel, so the tokio
+/// worker that called them stays free for other tasks while the work runs.
+/// Cheap to clone; clones share the same threads.
+#[derive(Clone)]
+pub struct ComputePool {
+    pool: Arc<ThreadPool>,
+}
+
+impl ComputePool {
+    /// Starts `threads` compute threads; 0 uses one per CPU.
+    pub fn new(threads: usize) -> Result<ComputePool, ThreadPoolBuildError> {
+        let pool = ThreadPoolBuilder::new()
+            .num_threads(threads)
+            .thread_name(|i| format!("llm-validator-compute-{}", i))
+            .build()?;
+        Ok(ComputePool { pool: Arc::new(pool) })
+    }
+
+    /// A process-wide pool with one thread per CPU, started on first use.
+    /// Used by guards that weren't given a pool of their own.
+    pub fn shared() -> ComputePool {
+        SHARED.get_or_init(|| ComputePool::new(0).expect("failed to start the shared compute pool")).clone()
+    }
+
+    pub fn threads(&self) -> usize {
+        self.pool.current_num_threads()
+    }
+
+    /// Runs `work` on the pool and waits for its result. A panic in `work` is
+    /// re-raised in the awaiting task instead of taking down the pool thread.
+    pub async fn run<F, T>(&self, work: F) -> T
+    where
+        F: FnOnce() -> T + Send + 'static,
+        T: Send + 'static,
+    {
+        match self.submit(work).await {
+            Ok(Ok(value)) => value,
+            Ok(Err(payload)) => panic::resume_unwind(payload),
+            Err(_) => unreachable!("compute pool dropped a job without replying"),
+        }
+    }
+
+    /// Starts `work` straight away and returns a channel for its result, so
+    /// several jobs can run at once and be awaited one by one. A panic in
+    /// `work` arrives as `Err`.
+    pub fn submit<F, T>(&self, work: F) -> oneshot::Receiver<thread::Result<T>>
+    where
+        F: FnOnce() -> T + Send + 'static,
+        T: Send + 'static,
+    {
+        let (reply, result) = oneshot::channel();
+        self.pool.spawn(move || {
+            let _ = reply.send(panic::catch_unwind(AssertUnwindSafe(work)));
+        });
+        result
+    }
+
+    /// Applies `f` to every item in parallel on the pool. Results come back in
+    /// the order of `items`, however the work was split.
+    pub async fn map<I, T, F>(&self, items: Vec<I>, f: F) -> Vec<T>
+    where
+        I: Send + 'static,
+        T: Send + 'static,
+        F: Fn(I) -> T + Send + Sync + 'static,
+    {
+        self.run(move || items.into_par_iter().map(f).collect()).await
+    }
+}
@@ -0,0 +1,930 @@
+use log::LevelFilter;
+use serde::{Deserialize, Serialize};
+use std::error::Error;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::alerts::Severity;
+use crate::compute::ComputePool;
+use crate::conversation::ConversationSettings;
+use crate::db::audit::AuditConfig;
+use crate::db::store::PatternStore;
+use crate::detectors::{Detector, DetectorConfig, DETECTOR_NAMES};
+use crate::guard::Guard;
+use crate::logging::ContentMode;
+use crate::policy::Policy;
+use crate::pool::{Overflow, PoolConfig};
+use crate::reputation::ReputationSettings;
+use crate::sources::SourceSpec;
+
+/// Config file read when neither `--config` nor `LLM_VALIDATOR_CONFIG` names one.
+pub const DEFAULT_CONFIG_PATH: &str = "llm_validator.toml";
+
+/// Every setting the validator reads, in one place.
+///
+/// Built in layers, each overriding the one before: the defaults below, then
+/// the TOML config file, then environment variables, then command line flags.
+/// Problems found in any layer are collected and reported together.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    /// off, error, warn, info, debug or trace.
+    pub log_level: String,
+    pub logging: LoggingConfig,
+    pub database: DatabaseConfig,
+    pub patterns: PatternStoreConfig,
+    pub detection: DetectionConfig,
+    pub models: ModelConfig,
+    pub workers: WorkerConfig,
+    pub server: ServerConfig,
+    pub sinks: SinkConfig,
+    pub stream: StreamConfig,
+    pub conversations: ConversationConfig,
+    pub reputation: ReputationConfig,
+    pub datasets: DatasetConfig,
+    pub tracing: TracingConfig,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct LoggingConfig {
+    /// text or json (one object per line).
+    pub format: String,
+    /// How message text appears in logs: hash, truncate or full. Anything
+    /// but hash can put prompts and leaked secrets in the logs.
+    pub content: String,
+    /// Characters kept when `content` is truncate.
+    pub truncate_chars: usize,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct DatabaseConfig {
+    pub url: Option<String>,
+    pub max_connections: u32,
+    pub connect_timeout_secs: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct PatternStoreConfig {
+    /// postgres, sqlite or memory.
+    pub backend: String,
+    pub sqlite_path: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct DetectionConfig {
+    pub detectors: Vec<String>,
+    /// Policy file with per-detector thresholds.
+    pub policy: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ModelConfig {
+    /// Pretrained tokenizer name or a local `tokenizer.json`.
+    pub tokenizer: String,
+    /// Directory holding a local sentiment model; the library default is
+    /// downloaded when unset.
+    pub sentiment_model: Option<PathBuf>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct WorkerConfig {
+    /// Threads that every command hands detector runs to, so matching never
+    /// blocks the async runtime; 0 uses one per CPU.
+    pub compute_threads: usize,
+    /// Records `scan` validates at a time.
+    pub scan_chunk: usize,
+    /// Tasks validating messages for `serve`; 0 uses one per CPU.
+    pub validators: usize,
+    /// Messages `serve` lets wait for a validator.
+    pub queue_depth: usize,
+    /// wait or reject: what happens to a message when the queue is full.
+    pub when_full: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ServerConfig {
+    pub listen: String,
+    /// Where `serve` exposes Prometheus metrics; off when unset.
+    pub metrics_listen: Option<String>,
+    /// How long `serve` waits, on shutdown, for the queue to drain and again
+    /// for the audit trail to flush.
+    pub shutdown_timeout_secs: u64,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SinkConfig {
+    pub audit: AuditSinkConfig,
+    pub alerts: AlertsConfig,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct AuditSinkConfig {
+    /// Records every message `serve`, `stream`, `check`, `scan` and
+    /// `documents` validate in `validation_runs`.
+    pub enabled: bool,
+    pub capacity: usize,
+    pub batch_size: usize,
+    pub flush_interval_ms: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct StreamConfig {
+    /// stdin, tcp://host:port, unix:///path, file:///path (followed as it
+    /// grows) or kafka://broker1,broker2/topic.
+    pub source: String,
+    /// stdout, file:///path or tcp://host:port: where verdicts are written.
+    pub sink: String,
+    /// Consumer group for kafka sources.
+    pub kafka_group: String,
+    /// Read a followed file from the top instead of only new lines.
+    pub tail_from_start: bool,
+    /// How often a followed file is checked for new lines.
+    pub poll_interval_ms: u64,
+}
+
+/// Multi-turn validation for messages that carry a `session_id`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ConversationConfig {
+    pub enabled: bool,
+    /// memory or database; the database keeps sessions across restarts and
+    /// instances.
+    pub store: String,
+    pub window_turns: usize,
+    pub window_chars: usize,
+    /// Share of a session's risk carried into its next turn, from 0 to 1.
+    pub risk_decay: f32,
+    /// Risk at which a session's turns are blocked; 0 turns this off.
+    pub risk_threshold: f32,
+    /// Memory store only: sessions idle for longer are forgotten.
+    pub idle_timeout_secs: u64,
+    /// Database store only: write the turns' text to `conversation_turns`,
+    /// which joining turns across processes needs. Off by default since it
+    /// puts raw prompts and responses in the database.
+    pub store_content: bool,
+}
+
+/// Per-client reputation: clients whose messages keep getting blocked are
+/// warned about, then throttled, then blocked for a while.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ReputationConfig {
+    pub enabled: bool,
+    /// memory or database; the database keeps reputations across restarts
+    /// and instances.
+    pub store: String,
+    /// Seconds for a client's risk to halve.
+    pub half_life_secs: u64,
+    pub warn_at: f64,
+    pub throttle_at: f64,
+    pub block_at: f64,
+    /// How long a block lasts once risk reaches `block_at`.
+    pub block_secs: u64,
+    /// Messages a throttled client may send per minute.
+    pub throttle_per_minute: u32,
+    /// Risk a blocked message adds per rule name, e.g.
+    /// `"Prompt Injection" = 3.0`; rules not listed add 1.
+    pub weights: BTreeMap<String, f64>,
+    /// Database store only: seconds between saves.
+    pub flush_interval_secs: u64,
+}
+
+/// Alerts raised for blocked messages. Off until at least one of `webhook`,
+/// `syslog` or `file` is configured.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct AlertsConfig {
+    /// low, medium, high or critical: alerts below this are not sent.
+    pub min_severity: String,
+    /// Severity of rules missing from `severities`.
+    pub default_severity: String,
+    /// Severity per rule name, e.g. `"Prompt Injection" = "high"`.
+    pub severities: BTreeMap<String, String>,
+    /// The same rule on the same message text alerts once per window.
+    pub dedup_window_secs: u64,
+    /// Alerts sent per rule per minute; 0 is unlimited.
+    pub rate_limit_per_minute: u32,
+    /// Alerts waiting for delivery before new ones are dropped.
+    pub capacity: usize,
+    pub webhook: Option<WebhookSinkConfig>,
+    pub syslog: Option<SyslogSinkConfig>,
+    pub file: Option<AlertFileSinkConfig>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct WebhookSinkConfig {
+    pub url: String,
+    /// Signs each body with HMAC-SHA256 in `X-Signature-256` when set.
+    #[serde(default)]
+    pub secret: Option<String>,
+    #[serde(default = "default_webhook_timeout_secs")]
+    pub timeout_secs: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SyslogSinkConfig {
+    /// host:port of the syslog receiver or SIEM collector.
+    pub address: String,
+    /// udp or tcp.
+    #[serde(default = "default_syslog_protocol")]
+    pub protocol: String,
+    /// cef or leef.
+    #[serde(default = "default_syslog_format")]
+    pub format: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct AlertFileSinkConfig {
+    /// NDJSON file alerts are appended to.
+    pub path: String,
+    /// Size at which the file is rotated to `path.1`.
+    #[serde(default = "default_alert_file_max_bytes")]
+    pub max_bytes: u64,
+    /// Rotated files kept.
+    #[serde(default = "default_alert_file_keep")]
+    pub keep: usize,
+}
+
+fn default_webhook_timeout_secs() -> u64 {
+    5
+}
+
+fn default_syslog_protocol() -> String {
+    "udp".to_string()
+}
+
+fn default_syslog_format() -> String {
+    "cef".to_string()
+}
+
+fn default_alert_file_max_bytes() -> u64 {
+    10 * 1024 * 1024
+}
+
+fn default_alert_file_keep() -> usize {
+    5
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct DatasetConfig {
+    /// Where imported datasets are stored.
+    pub dir: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct TracingConfig {
+    /// OTLP/gRPC collector, e.g. `http://localhost:4317`; tracing is off
+    /// when unset.
+    pub otlp_endpoint: Option<String>,
+    pub service_name: String,
+    /// Share of new traces kept, from 0 to 1. Traces started upstream follow
+    /// the caller's sampling decision.
+    pub sample_ratio: f64,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            log_level: "info".to_string(),
+            logging: LoggingConfig::default(),
+            database: DatabaseConfig::default(),
+            patterns: PatternStoreConfig::default(),
+            detection: DetectionConfig::default(),
+            models: ModelConfig::default(),
+            workers: WorkerConfig::default(),
+            server: ServerConfig::default(),
+            sinks: SinkConfig::default(),
+            stream: StreamConfig::default(),
+            conversations: ConversationConfig::default(),
+            reputation: ReputationConfig::default(),
+            datasets: DatasetConfig::default(),
+            tracing: TracingConfig::default(),
+        }
+    }
+}
+
+impl Default for LoggingConfig {
+    fn default() -> Self {
+        LoggingConfig {
+            format: "text".to_string(),
+            content: "hash".to_string(),
+            truncate_chars: 32,
+        }
+    }
+}
+
+impl Default for DatabaseConfig {
+    fn default() -> Self {
+        DatabaseConfig {
+            url: None,
+            max_connections: 10,
+            connect_timeout_secs: 30,
+        }
+    }
+}
+
+impl Default for PatternStoreConfig {
+    fn default() -> Self {
+        PatternStoreConfig {
+            backend: "postgres".to_string(),
+            sqlite_path: "llm_validator.db".to_string(),
+        }
+    }
+}
+
+impl Default for DetectionConfig {
+    fn default() -> Self {
+        DetectionConfig {
+            detectors: DETECTOR_NAMES.iter().map(|n| n.to_string()).collect(),
+            policy: None,
+        }
+    }
+}
+
+impl Default for ModelConfig {
+    fn default() -> Self {
+        ModelConfig {
+            tokenizer: "bert-base-uncased".to_string(),
+            sentiment_model: None,
+        }
+    }
+}
+
+impl Default for WorkerConfig {
+    fn default() -> Self {
+        WorkerConfig {
+            compute_threads: 0,
+            scan_chunk: 4096,
+            validators: 0,
+            queue_depth: 1024,
+            when_full: "wait".to_string(),
+        }
+    }
+}
+
+impl Default for ServerConfig {
+    fn default() -> Self {
+        ServerConfig {
+            listen: "127.0.0.1:7878".to_string(),
+            metrics_listen: None,
+            shutdown_timeout_secs: 5,
+        }
+    }
+}
+
+impl Default for AuditSinkConfig {
+    fn default() -> Self {
+        let defaults = AuditConfig::default();
+        AuditSinkConfig {
+            enabled: true,
+            capacity: defaults.capacity,
+            batch_size: defaults.batch_size,
+            flush_interval_ms: defaults.flush_interval.as_millis() as u64,
+        }
+    }
+}
+
+impl Default for ConversationConfig {
+    fn default() -> Self {
+        let defaults = ConversationSettings::default();
+        ConversationConfig {
+            enabled: false,
+            store: "memory".to_string(),
+            window_turns: defaults.window_turns,
+            window_chars: defaults.window_chars,
+            risk_decay: defaults.risk_decay,
+            risk_threshold: defaults.risk_threshold,
+            idle_timeout_secs: 3600,
+            store_content: false,
+        }
+    }
+}
+
+impl Default for ReputationConfig {
+    fn default() -> Self {
+        let defaults = ReputationSettings::default();
+        ReputationConfig {
+            enabled: false,
+            store: "memory".to_string(),
+            half_life_secs: defaults.half_life.as_secs(),
+            warn_at: defaults.warn_at,
+            throttle_at: defaults.throttle_at,
+            block_at: defaults.block_at,
+            block_secs: defaults.block_for.as_secs(),
+            throttle_per_minute: defaults.throttle_per_minute,
+            weights: defaults.weights,
+            flush_interval_secs: 10,
+        }
+    }
+}
+
+impl Default for StreamConfig {
+    fn default() -> Self {
+        StreamConfig {
+            source: "stdin".to_string(),
+            sink: "stdout".to_string(),
+            kafka_group: "llm_validator".to_string(),
+            tail_from_start: false,
+            poll_interval_ms: 250,
+        }
+    }
+}
+
+impl Default for AlertsConfig {
+    fn default() -> Self {
+        AlertsConfig {
+            min_severity: "low".to_string(),
+            default_severity: "medium".to_string(),
+            severities: BTreeMap::new(),
+            dedup_window_secs: 300,
+            rate_limit_per_minute: 60,
+            capacity: 1024,
+            webhook: None,
+            syslog: None,
+            file: None,
+        }
+    }
+}
+
+impl Default for TracingConfig {
+    fn default() -> Self {
+        TracingConfig {
+            otlp_endpoint: None,
+            service_name: "llm_validator".to_string(),
+            sample_ratio: 1.0,
+        }
+    }
+}
+
+impl Default for DatasetConfig {
+    fn default() -> Self {
+        DatasetConfig {
+            dir: "datasets".to_string(),
+        }
+    }
+}
+
+/// Every problem found while loading the configuration.
+#[derive(Debug)]
+pub struct ConfigError {
+    pub problems: Vec<String>,
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "invalid configuration:")?;
+        for problem in &self.problems {
+            write!(f, "\n  - {}", problem)?;
+        }
+        Ok(())
+    }
+}
+
+impl Error for ConfigError {}
+
+/// Reads environment variables into config fields, recording values that
+/// don't parse instead of stopping at the first one.
+struct EnvLayer<'a> {
+    lookup: &'a dyn Fn(&str) -> Option<String>,
+    problems: Vec<String>,
+}
+
+impl EnvLayer<'_> {
+    /// The value of the last variable in `names` that is set, so the
+    /// `LLM_VALIDATOR_` name wins over an older alias listed before it.
+    fn get(&self, names: &[&str]) -> Option<(String, String)> {
+        names.iter().rev().find_map(|name| (self.lookup)(name).map(|value| (name.to_string(), value)))
+    }
+
+    fn parse<T: FromStr>(&mut self, names: &[&str], target: &mut T)
+    where
+        T::Err: fmt::Display,
+    {
+        if let Some((name, value)) = self.get(names) {
+            match value.parse() {
+                Ok(parsed) => *target = parsed,
+                Err(e) => self.problems.push(format!("{}='{}': {}", name, value, e)),
+            }
+        }
+    }
+
+    fn optional<T: FromStr>(&mut self, names: &[&str], target: &mut Option<T>)
+    where
+        T::Err: fmt::Display,
+    {
+        if let Some((name, value)) = self.get(names) {
+            if value.is_empty() {
+                *target = None;
+                return;
+            }
+            match value.parse() {
+                Ok(parsed) => *target = Some(parsed),
+                Err(e) => self.problems.push(format!("{}='{}': {}", name, value, e)),
+            }
+        }
+    }
+
+    fn list(&mut self, names: &[&str], target: &mut Vec<String>) {
+        if let Some((_, value)) = self.get(names) {
+            *target = split_list(&value);
+        }
+    }
+}
+
+/// Splits a comma separated list, dropping blanks.
+pub fn split_list(value: &str) -> Vec<String> {
+    value.split(',').map(str::trim).filter(|v| !v.is_empty()).map(String::from).collect()
+}
+
+impl Config {
+    /// Loads defaults, the config file and the process environment, then
+    /// lets `overrides` apply the command line flags and validates the
+    /// result. A broken file, bad environment variables and invalid settings
+    /// are all reported together in one error.
+    ///
+    /// With no explicit path, `llm_validator.toml` is read if it exists.
+    pub fn load(path: Option<&Path>, overrides: impl FnOnce(&mut Config)) -> Result<Config, ConfigError> {
+        Config::load_from(path, &|name| std::env::var(name).ok(), overrides)
+    }
+
+    pub fn load_from(path: Option<&Path>, lookup: &dyn Fn(&str) -> Option<String>, overrides: impl FnOnce(&mut Config)) -> Result<Config, ConfigError> {
+        let mut problems = Vec::new();
+        let file = match path {
+            Some(path) => Some(path),
+            None => Some(Path::new(DEFAULT_CONFIG_PATH)).filter(|path| path.exists()),
+        };
+        // The other layers are still checked on top of the defaults when the
+        // file can't be read.
+        let mut config = match file.map(Config::from_file) {
+            Some(Ok(config)) => config,
+            Some(Err(e)) => {
+                problems.extend(e.problems);
+                Config::default()
+            }
+            None => Config::default(),
+        };
+
+        problems.extend(config.apply_env(lookup));
+        overrides(&mut config);
+        if let Err(e) = config.validate() {
+            problems.extend(e.problems);
+        }
+        if !problems.is_empty() {
+            return Err(ConfigError { problems });
+        }
+        Ok(config)
+    }
+
+    pub fn from_file(path: &Path) -> Result<Config, ConfigError> {
+        let problem = |e: &dyn fmt::Display| ConfigError {
+            problems: vec![format!("{}: {}", path.display(), e)],
+        };
+        let content = fs::read_to_string(path).map_err(|e| problem(&e))?;
+        toml::from_str(&content).map_err(|e| problem(&e))
+    }
+
+    /// The environment layer. Each setting has an `LLM_VALIDATOR_` variable;
+    /// the variables older versions used (`DATABASE_URL`, `PATTERN_STORE`, ...)
+    /// still work but lose to the prefixed name.
+    fn apply_env(&mut self, lookup: &dyn Fn(&str) -> Option<String>) -> Vec<String> {
+        let mut env = EnvLayer { lookup, problems: Vec::new() };
+
+        env.parse(&["LLM_VALIDATOR_LOG_LEVEL"], &mut self.log_level);
+        env.parse(&["LLM_VALIDATOR_LOG_FORMAT"], &mut self.logging.format);
+        env.parse(&["LLM_VALIDATOR_LOG_CONTENT"], &mut self.logging.content);
+        env.parse(&["LLM_VALIDATOR_LOG_TRUNCATE_CHARS"], &mut self.logging.truncate_chars);
+        env.optional(&["DATABASE_URL", "LLM_VALIDATOR_DATABASE_URL"], &mut self.database.url);
+        env.parse(&["DATABASE_MAX_CONNECTIONS", "LLM_VALIDATOR_DATABASE_MAX_CONNECTIONS"], &mut self.database.max_connections);
+        env.parse(&["LLM_VALIDATOR_DATABASE_CONNECT_TIMEOUT_SECS"], &mut self.database.connect_timeout_secs);
+        env.parse(&["PATTERN_STORE", "LLM_VALIDATOR_PATTERN_STORE"], &mut self.patterns.backend);
+        env.parse(&["SQLITE_PATH", "LLM_VALIDATOR_SQLITE_PATH"], &mut self.patterns.sqlite_path);
+        env.list(&["LLM_VALIDATOR_DETECTORS"], &mut self.detection.detectors);
+        env.optional(&["LLM_VALIDATOR_POLICY"], &mut self.detection.policy);
+        env.parse(&["LLM_VALIDATOR_TOKENIZER"], &mut self.models.tokenizer);
+        env.optional(&["LLM_VALIDATOR_SENTIMENT_MODEL"], &mut self.models.sentiment_model);
+        env.parse(&["LLM_VALIDATOR_COMPUTE_THREADS"], &mut self.workers.compute_threads);
+        env.parse(&["LLM_VALIDATOR_SCAN_CHUNK"], &mut self.workers.scan_chunk);
+        env.parse(&["LLM_VALIDATOR_VALIDATORS"], &mut self.workers.validators);
+        env.parse(&["LLM_VALIDATOR_QUEUE_DEPTH"], &mut self.workers.queue_depth);
+        env.parse(&["LLM_VALIDATOR_WHEN_FULL"], &mut self.workers.when_full);
+        env.parse(&["LLM_VALIDATOR_LISTEN"], &mut self.server.listen);
+        env.optional(&["LLM_VALIDATOR_METRICS_LISTEN"], &mut self.server.metrics_listen);
+        env.parse(&["LLM_VALIDATOR_SHUTDOWN_TIMEOUT_SECS"], &mut self.server.shutdown_timeout_secs);
+        env.parse(&["LLM_VALIDATOR_AUDIT_ENABLED"], &mut self.sinks.audit.enabled);
+        env.parse(&["LLM_VALIDATOR_AUDIT_CAPACITY"], &mut self.sinks.audit.capacity);
+        env.parse(&["LLM_VALIDATOR_AUDIT_BATCH_SIZE"], &mut self.sinks.audit.batch_size);
+        env.parse(&["LLM_VALIDATOR_AUDIT_FLUSH_INTERVAL_MS"], &mut self.sinks.audit.flush_interval_ms);
+        env.parse(&["LLM_VALIDATOR_ALERT_MIN_SEVERITY"], &mut self.sinks.alerts.min_severity);
+        env.parse(&["LLM_VALIDATOR_ALERT_DEDUP_WINDOW_SECS"], &mut self.sinks.alerts.dedup_window_secs);
+        env.parse(&["LLM_VALIDATOR_ALERT_RATE_LIMIT"], &mut self.sinks.alerts.rate_limit_per_minute);
+        if let Some((_, url)) = env.get(&["LLM_VALIDATOR_ALERT_WEBHOOK_URL"]) {
+            match &mut self.sinks.alerts.webhook {
+                Some(webhook) => webhook.url = url,
+                None => {
+                    self.sinks.alerts.webhook = Some(WebhookSinkConfig {
+                        url,
+                        secret: None,
+                        timeout_secs: default_webhook_timeout_secs(),
+                    })
+                }
+            }
+        }
+        if let Some(webhook) = &mut self.sinks.alerts.webhook {
+            env.optional(&["LLM_VALIDATOR_ALERT_WEBHOOK_SECRET"], &mut webhook.secret);
+        }
+        env.parse(&["LLM_VALIDATOR_STREAM_SOURCE"], &mut self.stream.source);
+        env.parse(&["LLM_VALIDATOR_STREAM_SINK"], &mut self.stream.sink);
+        env.parse(&["LLM_VALIDATOR_KAFKA_GROUP"], &mut self.stream.kafka_group);
+        env.parse(&["LLM_VALIDATOR_CONVERSATIONS"], &mut self.conversations.enabled);
+        env.parse(&["LLM_VALIDATOR_SESSION_STORE"], &mut self.conversations.store);
+        env.parse(&["LLM_VALIDATOR_SESSION_RISK_THRESHOLD"], &mut self.conversations.risk_threshold);
+        env.parse(&["LLM_VALIDATOR_REPUTATION"], &mut self.reputation.enabled);
+        env.parse(&["LLM_VALIDATOR_REPUTATION_STORE"], &mut self.reputation.store);
+        env.parse(&["LLM_VALIDATOR_REPUTATION_BLOCK_SECS"], &mut self.reputation.block_secs);
+        env.parse(&["DATASET_DIR", "LLM_VALIDATOR_DATASET_DIR"], &mut self.datasets.dir);
+        env.optional(&["OTEL_EXPORTER_OTLP_ENDPOINT", "LLM_VALIDATOR_OTLP_ENDPOINT"], &mut self.tracing.otlp_endpoint);
+        env.parse(&["OTEL_SERVICE_NAME", "LLM_VALIDATOR_SERVICE_NAME"], &mut self.tracing.service_name);
+        env.parse(&["LLM_VALIDATOR_TRACE_SAMPLE_RATIO"], &mut self.tracing.sample_ratio);
+
+        env.problems
+    }
+
+    /// Checks the merged configuration and reports every invalid setting at
+    /// once.
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        let mut problems = Vec::new();
+
+        if LevelFilter::from_str(&self.log_level).is_err() {
+            problems.push(format!("log_level '{}' must be off, error, warn, info, debug or trace", self.log_level));
+        }
+        if !["text", "json"].contains(&self.logging.format.as_str()) {
+            problems.push(format!("logging.format '{}' must be text or json", self.logging.format));
+        }
+        if ContentMode::parse(&self.logging.content, self.logging.truncate_chars).is_none() {
+            problems.push(format!("logging.content '{}' must be hash, truncate or full", self.logging.content));
+        }
+        if let Some(url) = &self.database.url {
+            if !url.starts_with("postgres://") && !url.starts_with("postgresql://") {
+                problems.push("database.url must start with postgres:// or postgresql://".to_string());
+            }
+        }
+        if self.database.max_connections == 0 {
+            problems.push("database.max_connections must be at least 1".to_string());
+        }
+        if self.database.connect_timeout_secs == 0 {
+            problems.push("database.connect_timeout_secs must be at least 1".to_string());
+        }
+        if !["postgres", "sqlite", "memory"].contains(&self.patterns.backend.as_str()) {
+            problems.push(format!("patterns.backend '{}' must be postgres, sqlite or memory", self.patterns.backend));
+        }
+        if self.detection.detectors.is_empty() {
+            problems.push("detection.detectors must name at least one detector".to_string());
+        }
+        for name in &self.detection.detectors {
+            if !DETECTOR_NAMES.contains(&name.as_str()) {
+                problems.push(format!("detection.detectors: unknown detector '{}', expected one of {}", name, DETECTOR_NAMES.join(", ")));
+            }
+        }
+        if let Some(path) = &self.detection.policy {
+            if let Err(e) = Policy::load(path) {
+                problems.push(format!("detection.policy: {}", e));
+            }
+        }
+        if let Some(dir) = &self.models.sentiment_model {
+            if !dir.is_dir() {
+                problems.push(format!("models.sentiment_model: {} is not a directory", dir.display()));
+            }
+        }
+        if self.workers.scan_chunk == 0 {
+            problems.push("workers.scan_chunk must be at least 1".to_string());
+        }
+        if self.workers.queue_depth == 0 {
+            problems.push("workers.queue_depth must be at least 1".to_string());
+        }
+        if Overflow::parse(&self.workers.when_full).is_none() {
+            problems.push(format!("workers.when_full '{}' must be wait or reject", self.workers.when_full));
+        }
+        let listeners = Some(("server.listen", &self.server.listen)).into_iter().chain(self.server.metrics_listen.as_ref().map(|l| ("server.metrics_listen", l)));
+        for (key, listen) in listeners {
+            match listen.rsplit_once(':') {
+                Some((host, port)) if !host.is_empty() && port.parse::<u16>().is_ok() => {}
+                _ => problems.push(format!("{} '{}' must be host:port", key, listen)),
+            }
+        }
+        let audit = &self.sinks.audit;
+        if audit.capacity == 0 || audit.batch_size == 0 || audit.flush_interval_ms == 0 {
+            problems.push("sinks.audit capacity, batch_size and flush_interval_ms must all be at least 1".to_string());
+        }
+        if audit.batch_size > audit.capacity {
+            problems.push(format!("sinks.audit.batch_size {} is larger than capacity {}", audit.batch_size, audit.capacity));
+        }
+        let alerts = &self.sinks.alerts;
+        let severities = Some(("sinks.alerts.min_severity".to_string(), &alerts.min_severity))
+            .into_iter()
+            .chain(Some(("sinks.alerts.default_severity".to_string(), &alerts.default_severity)))
+            .chain(alerts.severities.iter().map(|(rule, s)| (format!("sinks.alerts.severities.\"{}\"", rule), s)));
+        for (key, severity) in severities {
+            if Severity::parse(severity).is_none() {
+                problems.push(format!("{} '{}' must be low, medium, high or critical", key, severity));
+            }
+        }
+        if alerts.capacity == 0 {
+            problems.push("sinks.alerts.capacity must be at least 1".to_string());
+        }
+        if let Some(webhook) = &alerts.webhook {
+            if !webhook.url.starts_with("http://") && !webhook.url.starts_with("https://") {
+                problems.push(format!("sinks.alerts.webhook.url '{}' must start with http:// or https://", webhook.url));
+            }
+        }
+        if let Some(syslog) = &alerts.syslog {
+            if !["udp", "tcp"].contains(&syslog.protocol.as_str()) {
+                problems.push(format!("sinks.alerts.syslog.protocol '{}' must be udp or tcp", syslog.protocol));
+            }
+            if !["cef", "leef"].contains(&syslog.format.as_str()) {
+                problems.push(format!("sinks.alerts.syslog.format '{}' must be cef or leef", syslog.format));
+            }
+        }
+        if let Some(file) = &alerts.file {
+            if file.max_bytes == 0 {
+                problems.push("sinks.alerts.file.max_bytes must be at least 1".to_string());
+            }
+        }
+
+        if let Err(e) = SourceSpec::parse(&self.stream.source) {
+            problems.push(format!("stream.source {}", e));
+        }
+        let sink = &self.stream.sink;
+        if sink != "stdout" && sink != "-" && !["file://", "tcp://"].iter().any(|scheme| sink.len() > scheme.len() && sink.starts_with(scheme)) {
+            problems.push(format!("stream.sink '{}' must be stdout, file:///path or tcp://host:port", sink));
+        }
+        if self.stream.poll_interval_ms == 0 {
+            problems.push("stream.poll_interval_ms must be at least 1".to_string());
+        }
+
+        let conversations = &self.conversations;
+        if !["memory", "database"].contains(&conversations.store.as_str()) {
+            problems.push(format!("conversations.store '{}' must be memory or database", conversations.store));
+        }
+        if conversations.window_turns == 0 {
+            problems.push("conversations.window_turns must be at least 1".to_string());
+        }
+        if !(0.0..=1.0).contains(&conversations.risk_decay) {
+            problems.push(format!("conversations.risk_decay {} must be between 0 and 1", conversations.risk_decay));
+        }
+        if conversations.risk_threshold < 0.0 {
+            problems.push(format!("conversations.risk_threshold {} must not be negative", conversations.risk_threshold));
+        }
+
+        let reputation = &self.reputation;
+        if !["memory", "database"].contains(&reputation.store.as_str()) {
+            problems.push(format!("reputation.store '{}' must be memory or database", reputation.store));
+        }
+        if reputation.half_life_secs == 0 {
+            problems.push("reputation.half_life_secs must be at least 1".to_string());
+        }
+        if !(0.0 < reputation.warn_at && reputation.warn_at <= reputation.throttle_at && reputation.throttle_at <= reputation.block_at) {
+            problems.push(format!(
+                "reputation thresholds must satisfy 0 < warn_at <= throttle_at <= block_at, got {}, {} and {}",
+                reputation.warn_at, reputation.throttle_at, reputation.block_at
+            ));
+        }
+        if reputation.throttle_per_minute == 0 {
+            problems.push("reputation.throttle_per_minute must be at least 1".to_string());
+        }
+        for (rule, weight) in &reputation.weights {
+            if *weight < 0.0 {
+                problems.push(format!("reputation.weights.\"{}\" {} must not be negative", rule, weight));
+            }
+        }
+        if reputation.flush_interval_secs == 0 {
+            problems.push("reputation.flush_interval_secs must be at least 1".to_string());
+        }
+
+        if let Some(endpoint) = &self.tracing.otlp_endpoint {
+            if !endpoint.starts_with("http://") && !endpoint.starts_with("https://") {
+                problems.push(format!("tracing.otlp_endpoint '{}' must start with http:// or https://", endpoint));
+            }
+        }
+        if !(0.0..=1.0).contains(&self.tracing.sample_ratio) {
+            problems.push(format!("tracing.sample_ratio {} must be between 0 and 1", self.tracing.sample_ratio));
+        }
+
+        if problems.is_empty() {
+            Ok(())
+        } else {
+            Err(ConfigError { problems })
+        }
+    }
+
+    /// Only meaningful after `validate` has passed.
+    pub fn log_level_filter(&self) -> LevelFilter {
+        LevelFilter::from_str(&self.log_level).unwrap_or(LevelFilter::Info)
+    }
+}
+
+impl LoggingConfig {
+    pub fn content_mode(&self) -> ContentMode {
+        ContentMode::parse(&self.content, self.truncate_chars).unwrap_or(ContentMode::Hash)
+    }
+}
+
+impl DetectionConfig {
+    pub fn detector_config(&self) -> DetectorConfig {
+        DetectorConfig {
+            detectors: self.detectors.clone(),
+        }
+    }
+
+    /// Builds the detectors and loads the policy, defaulting every threshold
+    /// when no policy file is configured.
+    pub async fn load(&self, store: &dyn PatternStore) -> Result<(Vec<Box<dyn Detector>>, Policy), Box<dyn Error>> {
+        let detectors = self.detector_config().build(store).await?;
+        let policy = match &self.policy {
+            Some(path) => Policy::load(path)?,
+            None => Policy::default(),
+        };
+        Ok((detectors, policy))
+    }
+
+    /// A [`Guard`] running the configured detectors under the configured policy.
+    pub async fn guard(&self, store: Arc<dyn PatternStore>, compute: ComputePool) -> Result<Guard, Box<dyn Error>> {
+        let mut builder = Guard::builder()
+            .with_shared_store(store)
+            .with_detectors(self.detectors.clone())
+            .with_compute_pool(compute);
+        if let Some(path) = &self.policy {
+            builder = builder.with_policy(Policy::load(path)?);
+        }
+        Ok(builder.build().await?)
+    }
+}
+
+impl WorkerConfig {
+    pub fn compute_pool(&self) -> Result<ComputePool, Box<dyn Error>> {
+        Ok(ComputePool::new(self.compute_threads)?)
+    }
+
+    pub fn pool_config(&self) -> PoolConfig {
+        let defaults = PoolConfig::default();
+        PoolConfig {
+            workers: if self.validators == 0 { defaults.workers } else { self.validators },
+            queue_depth: self.queue_depth,
+            overflow: Overflow::parse(&self.when_full).unwrap_or(defaults.overflow),
+        }
+    }
+}
+
+impl ConversationConfig {
+    pub fn settings(&self) -> ConversationSettings {
+        ConversationSettings {
+            window_turns: self.window_turns,
+            window_chars: self.window_chars,
+            risk_decay: self.risk_decay,
+            risk_threshold: self.risk_threshold,
+        }
+    }
+}
+
+impl ReputationConfig {
+    pub fn settings(&self) -> ReputationSettings {
+        ReputationSettings {
+            half_life: Duration::from_secs(self.half_life_secs),
+            warn_at: self.warn_at,
+            throttle_at: self.throttle_at,
+            block_at: self.block_at,
+            block_for: Duration::from_secs(self.block_secs),
+            throttle_per_minute: self.throttle_per_minute,
+            weights: self.weights.clone(),
+        }
+    }
+}
+
+impl AuditSinkConfig {
+    pub fn audit_config(&self) -> AuditConfig {
+        AuditConfig {
+            capacity: self.capacity,
+            batch_size: self.batch_size,
+            flush_interval: Duration::from_millis(self.flush_interval_ms),
+        }
+    }
+}
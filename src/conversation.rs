@@ -0,0 +1,296 @@
+use async_trait::async_trait;
+use log::warn;
+use sqlx::Row;
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::sync::Mutex as AsyncMutex;
+
+use crate::config::ConversationConfig;
+use crate::db::store::StoreError;
+use crate::db::Database;
+use crate::findings::{Direction, Finding};
+use crate::guard::{Guard, Verdict};
+
+/// Rule of the finding added once a session's risk reaches the threshold.
+pub const SESSION_RISK_RULE: &str = "Session Risk";
+
+/// One message of a conversation, as kept in the rolling window.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Turn {
+    pub direction: Direction,
+    pub text: String,
+    pub blocked: bool,
+}
+
+/// What the validator remembers about a conversation between turns.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SessionState {
+    /// Most recent turns, oldest first.
+    pub turns: Vec<Turn>,
+    pub risk: f32,
+    /// Turns seen over the whole session, not just the window.
+    pub turn_count: u32,
+}
+
+/// Where conversation state lives between turns.
+#[async_trait]
+pub trait SessionStore: Send + Sync {
+    async fn load_session(&self, session_id: &str) -> Result<Option<SessionState>, StoreError>;
+
+    async fn save_session(&self, session_id: &str, state: &SessionState) -> Result<(), StoreError>;
+}
+
+/// Keeps sessions in this process, forgetting those idle for longer than
+/// `idle_timeout`.
+pub struct MemorySessionStore {
+    idle_timeout: Duration,
+    sessions: Mutex<HashMap<String, (SessionState, Instant)>>,
+}
+
+impl MemorySessionStore {
+    pub fn new(idle_timeout: Duration) -> Self {
+        MemorySessionStore {
+            idle_timeout,
+            sessions: Mutex::new(HashMap::new()),
+        }
+    }
+}
+
+#[async_trait]
+impl SessionStore for MemorySessionStore {
+    async fn load_session(&self, session_id: &str) -> Result<Option<SessionState>, StoreError> {
+        let mut sessions = self.sessions.lock().unwrap();
+        if sessions.get(session_id).map_or(false, |(_, touched)| touched.elapsed() > self.idle_timeout) {
+            sessions.remove(session_id);
+            return Ok(None);
+        }
+        Ok(sessions.get(session_id).map(|(state, _)| state.clone()))
+    }
+
+    async fn save_session(&self, session_id: &str, state: &SessionState) -> Result<(), StoreError> {
+        let mut sessions = self.sessions.lock().unwrap();
+        if sessions.len() >= 10_000 {
+            let idle_timeout = self.idle_timeout;
+            sessions.retain(|_, (_, touched)| touched.elapsed() <= idle_timeout);
+        }
+        sessions.insert(session_id.to_string(), (state.clone(), Instant::now()));
+        Ok(())
+    }
+}
+
+/// Sessions are rows of `sessions`, created on their first turn, with the
+/// window in `conversation_turns`. The turns' text is only written with
+/// `store_content`; otherwise it is left empty, and only the session's risk
+/// and the turns' verdicts carry over between processes.
+pub struct DatabaseSessionStore {
+    db: Database,
+    store_content: bool,
+}
+
+impl DatabaseSessionStore {
+    pub fn new(db: Database, store_content: bool) -> Self {
+        DatabaseSessionStore { db, store_content }
+    }
+}
+
+#[async_trait]
+impl SessionStore for DatabaseSessionStore {
+    async fn load_session(&self, session_id: &str) -> Result<Option<SessionState>, StoreError> {
+        let session = sqlx::query("SELECT risk_score, turn_count FROM sessions WHERE session_id = $1")
+            .bind(session_id)
+            .fetch_optional(&self.db.pool)
+            .await?;
+        let session = match session {
+            Some(row) => row,
+            None => return Ok(None),
+        };
+
+        let rows = sqlx::query("SELECT direction, content, blocked FROM conversation_turns WHERE session_id = $1 ORDER BY turn")
+            .bind(session_id)
+            .fetch_all(&self.db.pool)
+            .await?;
+        let turns = rows
+            .iter()
+            .map(|row| Turn {
+                direction: Direction::parse(row.get("direction")).unwrap_or(Direction::Input),
+                text: row.get("content"),
+                blocked: row.get("blocked"),
+            })
+            .collect();
+        let turn_count: i32 = session.get("turn_count");
+        Ok(Some(SessionState {
+            turns,
+            risk: session.get("risk_score"),
+            turn_count: turn_count.max(0) as u32,
+        }))
+    }
+
+    async fn save_session(&self, session_id: &str, state: &SessionState) -> Result<(), StoreError> {
+        if session_id.len() > 36 {
+            return Err(format!("session id '{}' is longer than 36 characters", session_id).into());
+        }
+
+        let mut tx = self.db.pool.begin().await?;
+        sqlx::query(
+            "INSERT INTO sessions (session_id, session_name, risk_score, turn_count) VALUES ($1, 'conversation', $2, $3)
+             ON CONFLICT (session_id) DO UPDATE SET risk_score = $2, turn_count = $3, updated_at = CURRENT_TIMESTAMP",
+        )
+        .bind(session_id)
+        .bind(state.risk)
+        .bind(state.turn_count as i32)
+        .execute(&mut *tx)
+        .await?;
+        sqlx::query("DELETE FROM conversation_turns WHERE session_id = $1")
+            .bind(session_id)
+            .execute(&mut *tx)
+            .await?;
+        let first = state.turn_count as usize - state.turns.len().min(state.turn_count as usize);
+        for (i, turn) in state.turns.iter().enumerate() {
+            sqlx::query("INSERT INTO conversation_turns (session_id, turn, direction, content, blocked) VALUES ($1, $2, $3, $4, $5)")
+                .bind(session_id)
+                .bind((first + i) as i32)
+                .bind(turn.direction.as_str())
+                .bind(if self.store_content { turn.text.as_str() } else { "" })
+                .bind(turn.blocked)
+                .execute(&mut *tx)
+                .await?;
+        }
+        tx.commit().await?;
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ConversationSettings {
+    /// Turns kept per session.
+    pub window_turns: usize,
+    /// Characters kept per session; the oldest turns go first.
+    pub window_chars: usize,
+    /// Share of the risk a session carries into its next turn.
+    pub risk_decay: f32,
+    /// Risk at which every turn of the session is blocked; 0 turns this off.
+    pub risk_threshold: f32,
+}
+
+impl Default for ConversationSettings {
+    fn default() -> Self {
+        ConversationSettings {
+            window_turns: 8,
+            window_chars: 4000,
+            risk_decay: 0.8,
+            risk_threshold: 3.0,
+        }
+    }
+}
+
+/// Validates messages as turns of a conversation rather than one at a time.
+pub struct Conversations {
+    store: Arc<dyn SessionStore>,
+    settings: ConversationSettings,
+    /// One lock per session with a turn in flight, so a session's turns are
+    /// checked one after another even when they reach different workers.
+    locks: Mutex<HashMap<String, Arc<AsyncMutex<()>>>>,
+}
+
+impl Conversations {
+    pub fn new(store: Arc<dyn SessionStore>, settings: ConversationSettings) -> Self {
+        Conversations {
+            store,
+            settings,
+            locks: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// From `[conversations]`, or `None` when they are off. The database
+    /// store needs `db`.
+    pub fn from_config(config: &ConversationConfig, db: Option<&Database>) -> Result<Option<Arc<Conversations>>, StoreError> {
+        if !config.enabled {
+            return Ok(None);
+        }
+        let store: Arc<dyn SessionStore> = match config.store.as_str() {
+            "database" => {
+                let db = db.ok_or("conversations.store = \"database\" needs database.url")?.clone();
+                Arc::new(DatabaseSessionStore::new(db, config.store_content))
+            }
+            _ => Arc::new(MemorySessionStore::new(Duration::from_secs(config.idle_timeout_secs))),
+        };
+        Ok(Some(Arc::new(Conversations::new(store, config.settings()))))
+    }
+
+    fn session_lock(&self, session_id: &str) -> Arc<AsyncMutex<()>> {
+        let mut locks = self.locks.lock().unwrap();
+        locks.retain(|_, lock| Arc::strong_count(lock) > 1);
+        locks.entry(session_id.to_string()).or_default().clone()
+    }
+
+    /// Checks one turn of a session. The turn is checked alone, then joined
+    /// with the session's earlier turns in the same direction that weren't
+    /// blocked, so an injection split across turns is caught once its pieces
+    /// meet. Rules only the joined text matches are added as findings with an
+    /// empty span.
+    ///
+    /// Every rule a turn matches adds 1 to the session's risk, which decays
+    /// by `risk_decay` each turn; once it reaches `risk_threshold` the turn
+    /// is blocked with a [`SESSION_RISK_RULE`] finding. If the store fails
+    /// the turn is still checked, alone.
+    pub async fn check(&self, guard: &Guard, session_id: &str, text: &str, direction: Direction, app: Option<&str>) -> Verdict {
+        let lock = self.session_lock(session_id);
+        let _turn = lock.lock().await;
+
+        let mut verdict = guard.check_message(text, direction, app).await;
+        let mut state = match self.store.load_session(session_id).await {
+            Ok(state) => state.unwrap_or_default(),
+            Err(e) => {
+                warn!(session_id = session_id; "Checking the turn alone, failed to load its session: {}", e);
+                return verdict;
+            }
+        };
+
+        // Turns loaded without their text have nothing to join.
+        let earlier: Vec<&str> = state
+            .turns
+            .iter()
+            .filter(|t| t.direction == direction && !t.blocked && !t.text.is_empty())
+            .map(|t| t.text.as_str())
+            .collect();
+        if !earlier.is_empty() {
+            let turns = earlier.len() + 1;
+            let joined = earlier.into_iter().chain(Some(text)).collect::<Vec<_>>().join(" ");
+            let across = guard.check_message(&joined, direction, app).await;
+            let failures: HashSet<&str> = across.failures.iter().map(|f| f.kind.rule()).collect();
+            let mut seen: HashSet<String> = verdict.findings.iter().map(|f| f.rule.clone()).collect();
+            for finding in &across.findings {
+                if !failures.contains(finding.rule.as_str()) && seen.insert(finding.rule.clone()) {
+                    let description = format!("Across the last {} turns: {}", turns, finding.description);
+                    verdict.findings.push(Finding::new(&finding.rule, &description, direction, 0, 0));
+                }
+            }
+        }
+
+        let rules: HashSet<&str> = verdict.findings.iter().map(|f| f.rule.as_str()).collect();
+        state.risk = state.risk * self.settings.risk_decay + rules.len() as f32;
+        if self.settings.risk_threshold > 0.0 && state.risk >= self.settings.risk_threshold {
+            let description = format!("Session risk {:.1} reached {:.1}", state.risk, self.settings.risk_threshold);
+            verdict.findings.push(Finding::new(SESSION_RISK_RULE, &description, direction, 0, 0));
+        }
+        verdict.blocked = !verdict.findings.is_empty();
+        verdict.session_risk = Some(state.risk);
+
+        state.turns.push(Turn {
+            direction,
+            text: text.to_string(),
+            blocked: verdict.blocked,
+        });
+        state.turn_count += 1;
+        while state.turns.len() > self.settings.window_turns.max(1)
+            || (state.turns.len() > 1 && state.turns.iter().map(|t| t.text.len()).sum::<usize>() > self.settings.window_chars)
+        {
+            state.turns.remove(0);
+        }
+        if let Err(e) = self.store.save_session(session_id, &state).await {
+            warn!(session_id = session_id; "Failed to save the session: {}", e);
+        }
+        verdict
+    }
+}
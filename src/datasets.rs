@@ -0,0 +1,347 @@
+use clap::Subcommand;
+use parquet::file::reader::{FileReader, SerializedFileReader};
+use parquet::record::Field;
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::fs::{self, File};
+use std::io::{BufRead, BufReader};
+use std::path::Path;
+
+use crate::config::DatasetConfig;
+use crate::db::experiments::Dataset;
+use crate::db::Database;
+use crate::eval::{Label, LabeledExample};
+use crate::findings::Direction;
+
+/// File formats accepted by `datasets import`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DatasetFormat {
+    Jsonl,
+    Csv,
+    Parquet,
+}
+
+impl DatasetFormat {
+    pub fn from_path(path: &str) -> Option<DatasetFormat> {
+        let extension = Path::new(path).extension()?.to_str()?.to_ascii_lowercase();
+        match extension.as_str() {
+            "jsonl" | "ndjson" => Some(DatasetFormat::Jsonl),
+            "csv" => Some(DatasetFormat::Csv),
+            "parquet" => Some(DatasetFormat::Parquet),
+            _ => None,
+        }
+    }
+}
+
+/// Partition an example belongs to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Split {
+    Train,
+    Validation,
+    Test,
+}
+
+impl Split {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Split::Train => "train",
+            Split::Validation => "validation",
+            Split::Test => "test",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Split> {
+        match value {
+            "train" => Some(Split::Train),
+            "validation" => Some(Split::Validation),
+            "test" => Some(Split::Test),
+            _ => None,
+        }
+    }
+}
+
+fn sha256_hex(bytes: &[u8]) -> String {
+    Sha256::digest(bytes).iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+fn text_bucket(text: &str) -> u64 {
+    let digest = Sha256::digest(text.as_bytes());
+    let mut prefix = [0u8; 8];
+    prefix.copy_from_slice(&digest[..8]);
+    u64::from_be_bytes(prefix)
+}
+
+/// Assigns an example to train (80%), validation (10%) or test (10%) from a
+/// hash of its text, so the split doesn't depend on file order and the same
+/// text lands in the same split in every dataset.
+pub fn split_for(text: &str) -> Split {
+    match text_bucket(text) % 100 {
+        0..=79 => Split::Train,
+        80..=89 => Split::Validation,
+        _ => Split::Test,
+    }
+}
+
+/// A row as it appears in an imported file, before its direction and label
+/// are normalized.
+#[derive(Debug, Deserialize)]
+struct RawExample {
+    text: String,
+    direction: String,
+    label: String,
+    #[serde(default)]
+    category: Option<String>,
+}
+
+impl RawExample {
+    fn normalize(self) -> Result<LabeledExample, Box<dyn Error>> {
+        Ok(LabeledExample {
+            text: self.text,
+            direction: parse_direction(&self.direction)?,
+            label: parse_label(&self.label)?,
+            category: self.category,
+        })
+    }
+}
+
+/// Reads labeled examples from a JSONL, CSV or Parquet file. Every format
+/// uses the columns `text`, `direction`, `label` and optionally `category`;
+/// directions and labels are matched ignoring case.
+pub fn read_examples(path: &str, format: DatasetFormat) -> Result<Vec<LabeledExample>, Box<dyn Error>> {
+    match format {
+        DatasetFormat::Jsonl => {
+            let reader = BufReader::new(File::open(path)?);
+            let mut examples = Vec::new();
+            for (number, line) in reader.lines().enumerate() {
+                let line = line?;
+                if line.trim().is_empty() {
+                    continue;
+                }
+                let example = serde_json::from_str::<RawExample>(&line)
+                    .map_err(Box::<dyn Error>::from)
+                    .and_then(RawExample::normalize)
+                    .map_err(|e| format!("{}:{}: {}", path, number + 1, e))?;
+                examples.push(example);
+            }
+            Ok(examples)
+        }
+        DatasetFormat::Csv => {
+            let mut reader = csv::Reader::from_path(path)?;
+            let mut examples = Vec::new();
+            for (number, record) in reader.deserialize::<RawExample>().enumerate() {
+                let example = record
+                    .map_err(Box::<dyn Error>::from)
+                    .and_then(RawExample::normalize)
+                    .map_err(|e| format!("{}: record {}: {}", path, number + 1, e))?;
+                examples.push(example);
+            }
+            Ok(examples)
+        }
+        DatasetFormat::Parquet => read_parquet(path),
+    }
+}
+
+fn read_parquet(path: &str) -> Result<Vec<LabeledExample>, Box<dyn Error>> {
+    let reader = SerializedFileReader::new(File::open(path)?)?;
+    let mut examples = Vec::new();
+
+    for (number, row) in reader.get_row_iter(None)?.enumerate() {
+        let row = row?;
+        let mut text = None;
+        let mut direction = None;
+        let mut label = None;
+        let mut category = None;
+        for (name, field) in row.get_column_iter() {
+            let value = match field {
+                Field::Str(value) => value.as_str(),
+                Field::Null => continue,
+                other => return Err(format!("{}: row {}: column '{}' must be a string, got {}", path, number + 1, name, other).into()),
+            };
+            match name.as_str() {
+                "text" => text = Some(value.to_string()),
+                "direction" => direction = Some(parse_direction(value)?),
+                "label" => label = Some(parse_label(value)?),
+                "category" => category = Some(value.to_string()),
+                _ => {}
+            }
+        }
+        let missing = |column: &str| format!("{}: row {}: missing '{}'", path, number + 1, column);
+        examples.push(LabeledExample {
+            text: text.ok_or_else(|| missing("text"))?,
+            direction: direction.ok_or_else(|| missing("direction"))?,
+            label: label.ok_or_else(|| missing("label"))?,
+            category,
+        });
+    }
+
+    Ok(examples)
+}
+
+fn parse_direction(value: &str) -> Result<Direction, Box<dyn Error>> {
+    Ok(serde_json::from_value(serde_json::Value::String(value.to_ascii_lowercase()))?)
+}
+
+fn parse_label(value: &str) -> Result<Label, Box<dyn Error>> {
+    Ok(serde_json::from_value(serde_json::Value::String(value.to_ascii_lowercase()))?)
+}
+
+/// Serializes examples as JSONL, the form every dataset is stored in.
+pub fn to_jsonl(examples: &[LabeledExample]) -> Result<String, Box<dyn Error>> {
+    let mut content = String::new();
+    for example in examples {
+        content.push_str(&serde_json::to_string(example)?);
+        content.push('\n');
+    }
+    Ok(content)
+}
+
+/// Reads a stored dataset, refusing it if the file no longer matches the hash
+/// recorded at import time.
+pub fn load_dataset(dataset: &Dataset) -> Result<Vec<LabeledExample>, Box<dyn Error>> {
+    let content = fs::read(&dataset.data_path)?;
+    if let Some(expected) = &dataset.content_sha256 {
+        let actual = sha256_hex(&content);
+        if &actual != expected {
+            return Err(format!("dataset {} at {} has been modified (sha256 {} != {})", dataset.dataset_id, dataset.data_path, actual, expected).into());
+        }
+    }
+    crate::eval::load_examples(&dataset.data_path)
+}
+
+/// Counts per label, direction, category and split.
+#[derive(Debug, Default, PartialEq)]
+pub struct DatasetStats {
+    pub total: usize,
+    pub by_label: BTreeMap<String, usize>,
+    pub by_direction: BTreeMap<String, usize>,
+    pub by_category: BTreeMap<String, usize>,
+    pub by_split: BTreeMap<String, usize>,
+}
+
+pub fn stats(examples: &[LabeledExample]) -> DatasetStats {
+    let mut stats = DatasetStats::default();
+    for example in examples {
+        stats.total += 1;
+        let label = if example.is_malicious() { "malicious" } else { "benign" };
+        *stats.by_label.entry(label.to_string()).or_default() += 1;
+        *stats.by_direction.entry(example.direction.as_str().to_string()).or_default() += 1;
+        let category = example.category.clone().unwrap_or_else(|| "(none)".to_string());
+        *stats.by_category.entry(category).or_default() += 1;
+        *stats.by_split.entry(split_for(&example.text).as_str().to_string()).or_default() += 1;
+    }
+    stats
+}
+
+/// Picks `count` examples in a fixed pseudo-random order, optionally from one
+/// split. The same dataset always gives the same sample.
+pub fn sample(examples: &[LabeledExample], count: usize, split: Option<Split>) -> Vec<LabeledExample> {
+    let mut candidates: Vec<&LabeledExample> = examples
+        .iter()
+        .filter(|e| split.map_or(true, |s| split_for(&e.text) == s))
+        .collect();
+    candidates.sort_by_key(|e| text_bucket(&e.text));
+    candidates.into_iter().take(count).cloned().collect()
+}
+
+pub fn parse_split(value: &str) -> Result<Split, String> {
+    Split::parse(value).ok_or_else(|| format!("expected train, validation or test, got '{}'", value))
+}
+
+#[derive(Debug, Subcommand)]
+pub enum DatasetsCommand {
+    /// Import a JSONL, CSV or Parquet corpus.
+    Import {
+        path: String,
+        name: String,
+        /// Attach to an existing session instead of creating one.
+        session_id: Option<String>,
+    },
+    /// List imported datasets.
+    List,
+    /// Count examples by label, direction, category and split.
+    Stats { dataset_id: i32 },
+    /// Print a deterministic sample of examples.
+    Sample {
+        dataset_id: i32,
+        #[arg(default_value_t = 10)]
+        count: usize,
+        #[arg(value_parser = parse_split)]
+        split: Option<Split>,
+    },
+}
+
+/// Handles `datasets import|list|stats|sample`.
+///
+/// Imports are normalized to JSONL under `datasets.dir` and named after their
+/// sha256, so re-importing the same corpus is caught.
+pub async fn run_datasets_command(command: &DatasetsCommand, config: &DatasetConfig, db: &Database) -> Result<(), Box<dyn Error>> {
+    match command {
+        DatasetsCommand::Import { path, name, session_id } => {
+            let format = DatasetFormat::from_path(path).ok_or_else(|| format!("{}: expected a .jsonl, .csv or .parquet file", path))?;
+
+            let examples = read_examples(path, format)?;
+            let content = to_jsonl(&examples)?;
+            let sha = sha256_hex(content.as_bytes());
+            if let Some(existing) = db.find_dataset_by_hash(&sha).await? {
+                return Err(format!("{} was already imported as dataset {} ('{}')", path, existing.dataset_id, existing.dataset_name).into());
+            }
+
+            fs::create_dir_all(&config.dir)?;
+            let stored = Path::new(&config.dir).join(format!("{}.jsonl", sha));
+            fs::write(&stored, &content)?;
+
+            // Don't leave a file behind that no dataset row points at.
+            let added: Result<i32, Box<dyn Error>> = async {
+                let session_id = match session_id {
+                    Some(id) => db.fetch_session(id).await?.session_id,
+                    None => db.create_session(name).await?,
+                };
+                Ok(db.add_dataset(&session_id, name, &stored.to_string_lossy(), &sha, examples.len() as i32).await?)
+            }
+            .await;
+            if added.is_err() {
+                let _ = fs::remove_file(&stored);
+            }
+            let dataset_id = added?;
+            println!("Imported {} examples as dataset {} (sha256 {})", examples.len(), dataset_id, sha);
+        }
+        DatasetsCommand::List => {
+            for d in db.fetch_all_datasets().await? {
+                println!(
+                    "{}\t{}\t{} examples\tsession {}\t{}",
+                    d.dataset_id,
+                    d.dataset_name,
+                    d.example_count,
+                    d.session_id,
+                    d.content_sha256.unwrap_or_default()
+                );
+            }
+        }
+        DatasetsCommand::Stats { dataset_id } => {
+            let dataset = db.fetch_dataset(*dataset_id).await?;
+            let stats = stats(&load_dataset(&dataset)?);
+            println!("{} ({} examples)", dataset.dataset_name, stats.total);
+            for (title, counts) in [
+                ("label", &stats.by_label),
+                ("direction", &stats.by_direction),
+                ("category", &stats.by_category),
+                ("split", &stats.by_split),
+            ] {
+                println!("{}:", title);
+                for (key, count) in counts {
+                    println!("  {:<24} {}", key, count);
+                }
+            }
+        }
+        DatasetsCommand::Sample { dataset_id, count, split } => {
+            let dataset = db.fetch_dataset(*dataset_id).await?;
+            for example in sample(&load_dataset(&dataset)?, *count, *split) {
+                println!("{}", serde_json::to_string(&example)?);
+            }
+        }
+    }
+
+    Ok(())
+}
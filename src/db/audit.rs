@@ -0,0 +1,381 @@
+use chrono::{NaiveDateTime, Utc};
+use log::{error, warn};
+use sqlx::{PgPool, Row};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+use tracing::Instrument;
+use uuid::Uuid;
+
+use crate::config::AuditSinkConfig;
+use crate::db::error::DbError;
+use crate::db::Database;
+use crate::findings::{Direction, Finding};
+
+/// Outcome of one validation run.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RunStatus {
+    Passed,
+    Blocked,
+    Error,
+}
+
+impl RunStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            RunStatus::Passed => "passed",
+            RunStatus::Blocked => "blocked",
+            RunStatus::Error => "error",
+        }
+    }
+}
+
+/// Everything the audit trail keeps about a single validated message.
+#[derive(Debug, Clone)]
+pub struct ValidationRecord {
+    pub run_uuid: String,
+    /// The request the message came in, to find the run from logs and alerts.
+    pub request_id: Option<String>,
+    pub app: Option<String>,
+    pub direction: Direction,
+    pub run_time: NaiveDateTime,
+    pub status: RunStatus,
+    pub duration_ms: i64,
+    pub findings: Vec<Finding>,
+}
+
+impl ValidationRecord {
+    /// Builds a record stamped with the current time. The status is `Blocked`
+    /// when there are findings and `Passed` otherwise.
+    pub fn new(direction: Direction, findings: Vec<Finding>, duration: Duration) -> Self {
+        let status = if findings.is_empty() { RunStatus::Passed } else { RunStatus::Blocked };
+        ValidationRecord {
+            run_uuid: Uuid::new_v4().to_string(),
+            request_id: None,
+            app: None,
+            direction,
+            run_time: Utc::now().naive_utc(),
+            status,
+            duration_ms: duration.as_millis() as i64,
+            findings,
+        }
+    }
+
+    pub fn failed(direction: Direction, duration: Duration) -> Self {
+        ValidationRecord {
+            status: RunStatus::Error,
+            ..ValidationRecord::new(direction, Vec::new(), duration)
+        }
+    }
+
+    /// Tags the record with its request and app. Both come from clients, so
+    /// they are cut to the 255 characters the columns hold.
+    pub fn for_request(mut self, request_id: &str, app: Option<&str>) -> Self {
+        self.request_id = Some(clip(request_id));
+        self.app = app.map(clip);
+        self
+    }
+}
+
+fn clip(value: &str) -> String {
+    value.chars().take(255).collect()
+}
+
+#[derive(Debug, Clone)]
+pub struct AuditConfig {
+    /// How many records can wait in memory before new ones are dropped.
+    pub capacity: usize,
+    /// Largest number of runs written in one transaction.
+    pub batch_size: usize,
+    /// A partial batch is flushed after this long.
+    pub flush_interval: Duration,
+}
+
+impl Default for AuditConfig {
+    fn default() -> Self {
+        AuditConfig {
+            capacity: 10_000,
+            batch_size: 256,
+            flush_interval: Duration::from_millis(500),
+        }
+    }
+}
+
+/// Handle used on the validation path to hand records to the background
+/// writer. Recording never waits on the database: when the queue is full the
+/// record is dropped and counted instead.
+#[derive(Clone)]
+pub struct AuditWriter {
+    sender: mpsc::Sender<ValidationRecord>,
+    dropped: Arc<AtomicU64>,
+}
+
+impl AuditWriter {
+    /// Starts the background writer. The returned task finishes once every
+    /// `AuditWriter` clone has been dropped and the remaining records are
+    /// flushed.
+    pub fn spawn(pool: PgPool, config: AuditConfig) -> (AuditWriter, JoinHandle<()>) {
+        let (sender, receiver) = mpsc::channel(config.capacity);
+        let handle = tokio::spawn(run_writer(pool, receiver, config));
+        let writer = AuditWriter {
+            sender,
+            dropped: Arc::new(AtomicU64::new(0)),
+        };
+        (writer, handle)
+    }
+
+    pub fn record(&self, record: ValidationRecord) {
+        if self.sender.try_send(record).is_err() {
+            let dropped = self.dropped.fetch_add(1, Ordering::Relaxed) + 1;
+            warn!("Audit queue full or closed, {} records dropped so far", dropped);
+        }
+    }
+
+    pub fn dropped(&self) -> u64 {
+        self.dropped.load(Ordering::Relaxed)
+    }
+
+    /// Starts a writer when `[sinks.audit]` is enabled and there is a
+    /// database to write to.
+    pub fn from_config(config: &AuditSinkConfig, db: Option<&Database>) -> Option<(AuditWriter, JoinHandle<()>)> {
+        db.filter(|_| config.enabled).map(|db| AuditWriter::spawn(db.pool.clone(), config.audit_config()))
+    }
+
+    /// Drops this handle and waits for the writer to flush what is queued.
+    /// Other clones still hold the writer open.
+    pub async fn finish(self, task: JoinHandle<()>) {
+        drop(self);
+        if let Err(e) = task.await {
+            error!("Audit writer failed: {}", e);
+        }
+    }
+}
+
+async fn run_writer(pool: PgPool, mut receiver: mpsc::Receiver<ValidationRecord>, config: AuditConfig) {
+    let mut batch = Vec::with_capacity(config.batch_size);
+    let mut ticker = tokio::time::interval(config.flush_interval);
+
+    loop {
+        tokio::select! {
+            message = receiver.recv() => match message {
+                Some(record) => {
+                    batch.push(record);
+                    if batch.len() >= config.batch_size {
+                        flush(&pool, &mut batch).await;
+                    }
+                }
+                None => break,
+            },
+            _ = ticker.tick() => {
+                if !batch.is_empty() {
+                    flush(&pool, &mut batch).await;
+                }
+            }
+        }
+    }
+
+    if !batch.is_empty() {
+        flush(&pool, &mut batch).await;
+    }
+}
+
+async fn flush(pool: &PgPool, batch: &mut Vec<ValidationRecord>) {
+    let span = tracing::info_span!("audit_write", records = batch.len());
+    if let Err(e) = write_batch(pool, batch).instrument(span).await {
+        error!("Failed to write {} audit records: {}", batch.len(), e);
+    }
+    batch.clear();
+}
+
+/// Writes a batch of runs and their findings in one transaction using
+/// `UNNEST` so the row count doesn't change the number of round trips.
+async fn write_batch(pool: &PgPool, batch: &[ValidationRecord]) -> Result<(), DbError> {
+    let mut run_uuids = Vec::with_capacity(batch.len());
+    let mut request_ids = Vec::with_capacity(batch.len());
+    let mut apps = Vec::with_capacity(batch.len());
+    let mut types = Vec::with_capacity(batch.len());
+    let mut times = Vec::with_capacity(batch.len());
+    let mut statuses = Vec::with_capacity(batch.len());
+    let mut errors = Vec::with_capacity(batch.len());
+    let mut durations = Vec::with_capacity(batch.len());
+
+    let mut finding_runs = Vec::new();
+    let mut rules = Vec::new();
+    let mut descriptions = Vec::new();
+    let mut directions = Vec::new();
+    let mut starts = Vec::new();
+    let mut ends = Vec::new();
+
+    for record in batch {
+        run_uuids.push(record.run_uuid.clone());
+        request_ids.push(record.request_id.clone().unwrap_or_default());
+        apps.push(record.app.clone().unwrap_or_default());
+        types.push(record.direction.as_str().to_string());
+        times.push(record.run_time);
+        statuses.push(record.status.as_str().to_string());
+        errors.push(record.findings.len() as i32);
+        durations.push(record.duration_ms);
+
+        for finding in &record.findings {
+            finding_runs.push(record.run_uuid.clone());
+            rules.push(finding.rule.clone());
+            descriptions.push(finding.description.clone());
+            directions.push(finding.direction.as_str().to_string());
+            starts.push(finding.start as i32);
+            ends.push(finding.end as i32);
+        }
+    }
+
+    let mut tx = pool.begin().await?;
+    sqlx::query(
+        "INSERT INTO validation_runs (run_uuid, validation_type, run_time, status, errors_found, duration_ms, request_id, app)
+         SELECT u, t, rt, s, e, d, NULLIF(req, ''), NULLIF(a, '')
+         FROM UNNEST($1::varchar[], $2::varchar[], $3::timestamp[], $4::varchar[], $5::int[], $6::bigint[], $7::varchar[], $8::varchar[])
+              AS batch (u, t, rt, s, e, d, req, a)",
+    )
+    .bind(&run_uuids)
+    .bind(&types)
+    .bind(&times)
+    .bind(&statuses)
+    .bind(&errors)
+    .bind(&durations)
+    .bind(&request_ids)
+    .bind(&apps)
+    .execute(&mut *tx)
+    .await?;
+
+    if !finding_runs.is_empty() {
+        sqlx::query(
+            "INSERT INTO validation_findings (run_uuid, rule, description, direction, span_start, span_end)
+             SELECT * FROM UNNEST($1::varchar[], $2::varchar[], $3::text[], $4::varchar[], $5::int[], $6::int[])",
+        )
+        .bind(&finding_runs)
+        .bind(&rules)
+        .bind(&descriptions)
+        .bind(&directions)
+        .bind(&starts)
+        .bind(&ends)
+        .execute(&mut *tx)
+        .await?;
+    }
+
+    tx.commit().await?;
+    Ok(())
+}
+
+/// Filters for `Database::query_validation_runs`. Unset fields match anything.
+#[derive(Debug, Clone, Default)]
+pub struct AuditQuery {
+    pub direction: Option<Direction>,
+    pub status: Option<RunStatus>,
+    /// Only runs with at least one finding for this rule.
+    pub rule: Option<String>,
+    pub request_id: Option<String>,
+    pub app: Option<String>,
+    pub since: Option<NaiveDateTime>,
+    pub until: Option<NaiveDateTime>,
+    pub limit: i64,
+}
+
+#[derive(Debug, Clone)]
+pub struct ValidationRunRow {
+    pub run_uuid: String,
+    pub request_id: Option<String>,
+    pub app: Option<String>,
+    pub validation_type: String,
+    pub run_time: NaiveDateTime,
+    pub status: String,
+    pub errors_found: i32,
+    pub duration_ms: i64,
+}
+
+#[derive(Debug, Clone)]
+pub struct FindingRow {
+    pub rule: String,
+    pub description: Option<String>,
+    pub direction: String,
+    pub span_start: i32,
+    pub span_end: i32,
+}
+
+impl Database {
+    /// Most recent runs first.
+    pub async fn query_validation_runs(&self, query: &AuditQuery) -> Result<Vec<ValidationRunRow>, DbError> {
+        let rows = sqlx::query(
+            "SELECT r.run_uuid, r.request_id, r.app, r.validation_type, r.run_time, r.status, r.errors_found, r.duration_ms
+             FROM validation_runs r
+             WHERE ($1::varchar IS NULL OR r.validation_type = $1)
+               AND ($2::varchar IS NULL OR r.status = $2)
+               AND ($3::timestamp IS NULL OR r.run_time >= $3)
+               AND ($4::timestamp IS NULL OR r.run_time < $4)
+               AND ($5::varchar IS NULL OR EXISTS (
+                    SELECT 1 FROM validation_findings f WHERE f.run_uuid = r.run_uuid AND f.rule = $5))
+               AND ($7::varchar IS NULL OR r.request_id = $7)
+               AND ($8::varchar IS NULL OR r.app = $8)
+             ORDER BY r.run_time DESC
+             LIMIT $6",
+        )
+        .bind(query.direction.map(|d| d.as_str()))
+        .bind(query.status.map(|s| s.as_str()))
+        .bind(query.since)
+        .bind(query.until)
+        .bind(query.rule.as_deref())
+        .bind(query.limit)
+        .bind(query.request_id.as_deref())
+        .bind(query.app.as_deref())
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows
+            .iter()
+            .map(|row| ValidationRunRow {
+                run_uuid: row.get("run_uuid"),
+                request_id: row.get("request_id"),
+                app: row.get("app"),
+                validation_type: row.get("validation_type"),
+                run_time: row.get("run_time"),
+                status: row.get("status"),
+                errors_found: row.get("errors_found"),
+                duration_ms: row.get("duration_ms"),
+            })
+            .collect())
+    }
+
+    pub async fn findings_for_run(&self, run_uuid: &str) -> Result<Vec<FindingRow>, DbError> {
+        let rows = sqlx::query(
+            "SELECT rule, description, direction, span_start, span_end
+             FROM validation_findings WHERE run_uuid = $1 ORDER BY span_start",
+        )
+        .bind(run_uuid)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows
+            .iter()
+            .map(|row| FindingRow {
+                rule: row.get("rule"),
+                description: row.get("description"),
+                direction: row.get("direction"),
+                span_start: row.get("span_start"),
+                span_end: row.get("span_end"),
+            })
+            .collect())
+    }
+
+    /// Number of findings per rule since `since`, most frequent first.
+    pub async fn finding_counts_by_rule(&self, since: NaiveDateTime) -> Result<Vec<(String, i64)>, DbError> {
+        let rows = sqlx::query(
+            "SELECT f.rule, COUNT(*) AS hits
+             FROM validation_findings f JOIN validation_runs r ON r.run_uuid = f.run_uuid
+             WHERE r.run_time >= $1
+             GROUP BY f.rule ORDER BY hits DESC",
+        )
+        .bind(since)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows.iter().map(|row| (row.get("rule"), row.get("hits"))).collect())
+    }
+}
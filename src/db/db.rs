@@ -1,48 +1,39 @@
-use sqlx::{PgPool, postgres::PgRow};
-use sqlx::Row;
-use regex::Regex;
-use std::error::Error;
+use sqlx::postgres::PgPoolOptions;
+use sqlx::PgPool;
+use std::time::Duration;
 
+use crate::config::DatabaseConfig;
+use crate::db::error::DbError;
+
+/// The one handle to Postgres. Cloning is cheap and every clone shares the
+/// same connection pool, so the worker, the audit writer and the commands all
+/// draw from one set of connections.
+#[derive(Clone)]
 pub struct Database {
     pub pool: PgPool,
 }
 
 impl Database {
-    pub async fn new() -> Result<Selrf, Box<dyn Error>> {
-        let database_url = std::env::var("DATABASE_URL")?;
-        let pool = PgPool::connect(&database_url).await?;
-        Ok(Self { pool })
-
+    /// Connects with the configured URL, pool size and timeout.
+    pub async fn from_config(config: &DatabaseConfig) -> Result<Self, DbError> {
+        let database_url = config
+            .url
+            .as_deref()
+            .ok_or_else(|| DbError::Config("database.url is not set (config file, DATABASE_URL or LLM_VALIDATOR_DATABASE_URL)".to_string()))?;
+        Database::connect(database_url, config.max_connections, Duration::from_secs(config.connect_timeout_secs)).await
     }
 
-    pub async fetch_input_patterns(&self) -> Result<Vec<(Regex, String)>, Box<dyn Error>> {
-        let rows: Vec<PgRow> = sqlx::query("SELECT pattern, description FROM input_patterns")
-            .fetch_all(&self.pool)
-            .await?;
-
-        let mut patterns = Vec::new();
-        for row in rows {
-            let pattern_str: String == row.get("pattern");
-            let description: String == row.get("description");
-            let pattern = Regex::new(&pattern_str);
-            patterns.push((pattern, description));
-
-        }
-        Ok(patterns)
+    pub async fn connect(database_url: &str, max_connections: u32, connect_timeout: Duration) -> Result<Self, DbError> {
+        let pool = PgPoolOptions::new()
+            .max_connections(max_connections)
+            .connect_timeout(connect_timeout)
+            .connect(database_url)
+            .await
+            .map_err(DbError::Connection)?;
+        Ok(Self { pool })
     }
 
-    pub async fn fetch_output_patterns(&self) -> Result<Vec(Regex, String)>, Box<dyn Error>> {
-        let rows: Vec<PgRow> = sqlx::query("SELECT pattern, description FROM output_patterns")
-                .fetch_all(&self.pool)
-                .await?
-        
-        let mut patterns = Vec::new();
-        for row in rows {
-            let pattern_str: String = row.get("pattern");
-            let description: String = row.get("description");
-            let pattern = Regex::new(&pattern_str)?;
-            patterns.push((pattern, description))
-        }
-        Ok(patterns)
+    pub fn from_pool(pool: PgPool) -> Self {
+        Database { pool }
     }
-}
\ No newline at end of file
+}
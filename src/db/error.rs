@@ -0,0 +1,57 @@
+use std::error::Error;
+use std::fmt;
+
+/// Errors from the data-access layer.
+#[derive(Debug)]
+pub enum DbError {
+    /// Missing or invalid connection settings.
+    Config(String),
+    /// The pool couldn't reach the database.
+    Connection(sqlx::Error),
+    /// A query failed once connected.
+    Query(sqlx::Error),
+    /// A stored pattern isn't a valid regex.
+    InvalidPattern(regex::Error),
+    /// The schema is missing migrations or a migration failed.
+    Migration(String),
+    NotFound(String),
+}
+
+impl fmt::Display for DbError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DbError::Config(msg) => write!(f, "database configuration error: {}", msg),
+            DbError::Connection(e) => write!(f, "could not connect to database: {}", e),
+            DbError::Query(e) => write!(f, "database query failed: {}", e),
+            DbError::InvalidPattern(e) => write!(f, "invalid pattern: {}", e),
+            DbError::Migration(msg) => write!(f, "{}", msg),
+            DbError::NotFound(what) => write!(f, "{} not found", what),
+        }
+    }
+}
+
+impl Error for DbError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            DbError::Connection(e) | DbError::Query(e) => Some(e),
+            DbError::InvalidPattern(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<sqlx::Error> for DbError {
+    fn from(e: sqlx::Error) -> Self {
+        match e {
+            sqlx::Error::Io(_) | sqlx::Error::Tls(_) | sqlx::Error::PoolTimedOut | sqlx::Error::PoolClosed => DbError::Connection(e),
+            sqlx::Error::RowNotFound => DbError::NotFound("row".to_string()),
+            e => DbError::Query(e),
+        }
+    }
+}
+
+impl From<regex::Error> for DbError {
+    fn from(e: regex::Error) -> Self {
+        DbError::InvalidPattern(e)
+    }
+}
@@ -0,0 +1,300 @@
+use chrono::NaiveDateTime;
+use sqlx::postgres::PgRow;
+use sqlx::Row;
+use uuid::Uuid;
+
+use crate::db::error::DbError;
+use crate::db::Database;
+
+#[derive(Debug, Clone)]
+pub struct Session {
+    pub session_id: String,
+    pub session_name: String,
+    pub created_at: NaiveDateTime,
+    pub updated_at: NaiveDateTime,
+}
+
+#[derive(Debug, Clone)]
+pub struct Dataset {
+    pub dataset_id: i32,
+    pub session_id: String,
+    pub dataset_name: String,
+    pub data_path: String,
+    pub format: String,
+    pub content_sha256: Option<String>,
+    pub example_count: i32,
+    pub created_at: NaiveDateTime,
+    pub updated_at: NaiveDateTime,
+}
+
+#[derive(Debug, Clone)]
+pub struct Model {
+    pub model_id: i32,
+    pub session_id: String,
+    pub model_name: String,
+    pub model_type: String,
+    pub model_params: serde_json::Value,
+    pub created_at: NaiveDateTime,
+    pub updated_at: NaiveDateTime,
+}
+
+/// A row of the `results` table.
+#[derive(Debug, Clone)]
+pub struct EvalResult {
+    pub result_id: i32,
+    pub model_id: i32,
+    pub session_id: String,
+    pub dataset_id: Option<i32>,
+    pub confusion: ConfusionMatrix,
+    pub accuracy: f32,
+    pub precision: f32,
+    pub recall: f32,
+    pub f1_score: f32,
+    pub created_at: NaiveDateTime,
+    pub updated_at: NaiveDateTime,
+}
+
+/// Scores to store for a model run.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Metrics {
+    pub accuracy: f32,
+    pub precision: f32,
+    pub recall: f32,
+    pub f1_score: f32,
+}
+
+/// Counts of predictions against labels, where "positive" means the message
+/// was flagged as malicious.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ConfusionMatrix {
+    pub true_positives: u32,
+    pub false_positives: u32,
+    pub true_negatives: u32,
+    pub false_negatives: u32,
+}
+
+impl ConfusionMatrix {
+    pub fn record(&mut self, predicted: bool, actual: bool) {
+        match (predicted, actual) {
+            (true, true) => self.true_positives += 1,
+            (true, false) => self.false_positives += 1,
+            (false, false) => self.true_negatives += 1,
+            (false, true) => self.false_negatives += 1,
+        }
+    }
+
+    pub fn total(&self) -> u32 {
+        self.true_positives + self.false_positives + self.true_negatives + self.false_negatives
+    }
+
+    /// Accuracy, precision, recall and F1. A ratio with a zero denominator is 0.
+    pub fn metrics(&self) -> Metrics {
+        let ratio = |num: u32, den: u32| if den == 0 { 0.0 } else { num as f32 / den as f32 };
+        let precision = ratio(self.true_positives, self.true_positives + self.false_positives);
+        let recall = ratio(self.true_positives, self.true_positives + self.false_negatives);
+        let f1_score = if precision + recall == 0.0 { 0.0 } else { 2.0 * precision * recall / (precision + recall) };
+        Metrics {
+            accuracy: ratio(self.true_positives + self.true_negatives, self.total()),
+            precision,
+            recall,
+            f1_score,
+        }
+    }
+}
+
+const DATASET_SELECT: &str = "SELECT dataset_id, session_id, dataset_name, data_path, format, content_sha256, example_count, created_at, updated_at FROM datasets";
+
+fn session_from_row(row: &PgRow) -> Session {
+    Session {
+        session_id: row.get("session_id"),
+        session_name: row.get("session_name"),
+        created_at: row.get("created_at"),
+        updated_at: row.get("updated_at"),
+    }
+}
+
+fn dataset_from_row(row: &PgRow) -> Dataset {
+    Dataset {
+        dataset_id: row.get("dataset_id"),
+        session_id: row.get("session_id"),
+        dataset_name: row.get("dataset_name"),
+        data_path: row.get("data_path"),
+        format: row.get("format"),
+        content_sha256: row.get("content_sha256"),
+        example_count: row.get("example_count"),
+        created_at: row.get("created_at"),
+        updated_at: row.get("updated_at"),
+    }
+}
+
+fn result_from_row(row: &PgRow) -> EvalResult {
+    let count = |column: &str| row.get::<i32, _>(column) as u32;
+    EvalResult {
+        result_id: row.get("result_id"),
+        model_id: row.get("model_id"),
+        session_id: row.get("session_id"),
+        dataset_id: row.get("dataset_id"),
+        confusion: ConfusionMatrix {
+            true_positives: count("true_positives"),
+            false_positives: count("false_positives"),
+            true_negatives: count("true_negatives"),
+            false_negatives: count("false_negatives"),
+        },
+        accuracy: row.get("accuracy"),
+        precision: row.get("precision"),
+        recall: row.get("recall"),
+        f1_score: row.get("f1_score"),
+        created_at: row.get("created_at"),
+        updated_at: row.get("updated_at"),
+    }
+}
+
+impl Database {
+    /// Creates a session and returns its id.
+    pub async fn create_session(&self, session_name: &str) -> Result<String, DbError> {
+        let session_id = Uuid::new_v4().to_string();
+        sqlx::query("INSERT INTO sessions (session_id, session_name) VALUES ($1, $2)")
+            .bind(&session_id)
+            .bind(session_name)
+            .execute(&self.pool)
+            .await?;
+        Ok(session_id)
+    }
+
+    pub async fn fetch_sessions(&self) -> Result<Vec<Session>, DbError> {
+        let rows = sqlx::query("SELECT session_id, session_name, created_at, updated_at FROM sessions ORDER BY created_at")
+            .fetch_all(&self.pool)
+            .await?;
+        Ok(rows.iter().map(session_from_row).collect())
+    }
+
+    pub async fn fetch_session(&self, session_id: &str) -> Result<Session, DbError> {
+        let row = sqlx::query("SELECT session_id, session_name, created_at, updated_at FROM sessions WHERE session_id = $1")
+            .bind(session_id)
+            .fetch_optional(&self.pool)
+            .await?
+            .ok_or_else(|| DbError::NotFound(format!("session {}", session_id)))?;
+        Ok(session_from_row(&row))
+    }
+
+    /// Adds a dataset to a session and returns its id.
+    pub async fn add_dataset(&self, session_id: &str, dataset_name: &str, data_path: &str, content_sha256: &str, example_count: i32) -> Result<i32, DbError> {
+        let row = sqlx::query(
+            "INSERT INTO datasets (session_id, dataset_name, data_path, format, content_sha256, example_count)
+             VALUES ($1, $2, $3, 'jsonl', $4, $5) RETURNING dataset_id",
+        )
+        .bind(session_id)
+        .bind(dataset_name)
+        .bind(data_path)
+        .bind(content_sha256)
+        .bind(example_count)
+        .fetch_one(&self.pool)
+        .await?;
+        Ok(row.get("dataset_id"))
+    }
+
+    pub async fn find_dataset_by_hash(&self, content_sha256: &str) -> Result<Option<Dataset>, DbError> {
+        let row = sqlx::query(&format!("{} WHERE content_sha256 = $1", DATASET_SELECT))
+            .bind(content_sha256)
+            .fetch_optional(&self.pool)
+            .await?;
+        Ok(row.as_ref().map(dataset_from_row))
+    }
+
+    pub async fn fetch_all_datasets(&self) -> Result<Vec<Dataset>, DbError> {
+        let rows = sqlx::query(&format!("{} ORDER BY dataset_id", DATASET_SELECT))
+            .fetch_all(&self.pool)
+            .await?;
+        Ok(rows.iter().map(dataset_from_row).collect())
+    }
+
+    pub async fn fetch_dataset(&self, dataset_id: i32) -> Result<Dataset, DbError> {
+        let row = sqlx::query(&format!("{} WHERE dataset_id = $1", DATASET_SELECT))
+            .bind(dataset_id)
+            .fetch_optional(&self.pool)
+            .await?
+            .ok_or_else(|| DbError::NotFound(format!("dataset {}", dataset_id)))?;
+        Ok(dataset_from_row(&row))
+    }
+
+    pub async fn fetch_datasets(&self, session_id: &str) -> Result<Vec<Dataset>, DbError> {
+        let rows = sqlx::query(&format!("{} WHERE session_id = $1 ORDER BY dataset_id", DATASET_SELECT))
+            .bind(session_id)
+            .fetch_all(&self.pool)
+            .await?;
+        Ok(rows.iter().map(dataset_from_row).collect())
+    }
+
+    /// Adds a model to a session and returns its id.
+    pub async fn add_model(&self, session_id: &str, model_name: &str, model_type: &str, model_params: &serde_json::Value) -> Result<i32, DbError> {
+        let row = sqlx::query(
+            "INSERT INTO models (session_id, model_name, model_type, model_params)
+             VALUES ($1, $2, $3, $4) RETURNING model_id",
+        )
+        .bind(session_id)
+        .bind(model_name)
+        .bind(model_type)
+        .bind(model_params)
+        .fetch_one(&self.pool)
+        .await?;
+        Ok(row.get("model_id"))
+    }
+
+    pub async fn fetch_model(&self, model_id: i32) -> Result<Model, DbError> {
+        let row = sqlx::query(
+            "SELECT model_id, session_id, model_name, model_type, model_params, created_at, updated_at
+             FROM models WHERE model_id = $1",
+        )
+        .bind(model_id)
+        .fetch_optional(&self.pool)
+        .await?
+        .ok_or_else(|| DbError::NotFound(format!("model {}", model_id)))?;
+
+        Ok(Model {
+            model_id: row.get("model_id"),
+            session_id: row.get("session_id"),
+            model_name: row.get("model_name"),
+            model_type: row.get("model_type"),
+            model_params: row.get("model_params"),
+            created_at: row.get("created_at"),
+            updated_at: row.get("updated_at"),
+        })
+    }
+
+    /// Stores the confusion matrix and scores of a model run on a dataset and
+    /// returns the result id.
+    pub async fn add_result(&self, session_id: &str, model_id: i32, dataset_id: Option<i32>, confusion: &ConfusionMatrix) -> Result<i32, DbError> {
+        let metrics = confusion.metrics();
+        let row = sqlx::query(
+            "INSERT INTO results (model_id, session_id, dataset_id, accuracy, precision, recall, f1_score,
+                                  true_positives, false_positives, true_negatives, false_negatives)
+             VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11) RETURNING result_id",
+        )
+        .bind(model_id)
+        .bind(session_id)
+        .bind(dataset_id)
+        .bind(metrics.accuracy)
+        .bind(metrics.precision)
+        .bind(metrics.recall)
+        .bind(metrics.f1_score)
+        .bind(confusion.true_positives as i32)
+        .bind(confusion.false_positives as i32)
+        .bind(confusion.true_negatives as i32)
+        .bind(confusion.false_negatives as i32)
+        .fetch_one(&self.pool)
+        .await?;
+        Ok(row.get("result_id"))
+    }
+
+    pub async fn fetch_results(&self, session_id: &str) -> Result<Vec<EvalResult>, DbError> {
+        let rows = sqlx::query(
+            "SELECT result_id, model_id, session_id, dataset_id, accuracy, precision, recall, f1_score,
+                    true_positives, false_positives, true_negatives, false_negatives, created_at, updated_at
+             FROM results WHERE session_id = $1 ORDER BY created_at",
+        )
+        .bind(session_id)
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(rows.iter().map(result_from_row).collect())
+    }
+}
@@ -0,0 +1,182 @@
+use clap::Subcommand;
+use sqlx::{Executor, Row};
+use std::error::Error;
+
+use crate::db::error::DbError;
+use crate::db::Database;
+
+/// A schema change compiled into the binary.
+pub struct Migration {
+    pub version: i64,
+    pub name: &'static str,
+    pub up: &'static str,
+    pub down: &'static str,
+}
+
+macro_rules! migration {
+    ($version:expr, $dir:literal) => {
+        Migration {
+            version: $version,
+            name: $dir,
+            up: include_str!(concat!("migrations/", $dir, "/up.sql")),
+            down: include_str!(concat!("migrations/", $dir, "/down.sql")),
+        }
+    };
+}
+
+/// Every migration, oldest first. New migrations are appended here with the
+/// next version number; released ones are never edited.
+pub const MIGRATIONS: &[Migration] = &[
+    migration!(1, "0001_sessions"),
+    migration!(2, "0002_patterns"),
+    migration!(3, "0003_pattern_history"),
+    migration!(4, "0004_validation_audit"),
+    migration!(5, "0005_result_confusion"),
+    migration!(6, "0006_dataset_ingestion"),
+    migration!(7, "0007_conversations"),
+    migration!(8, "0008_client_reputation"),
+    migration!(9, "0009_audit_context"),
+];
+
+impl Database {
+    async fn ensure_migrations_table(&self) -> Result<(), DbError> {
+        self.pool
+            .execute(
+                "CREATE TABLE IF NOT EXISTS schema_migrations (
+                    version BIGINT PRIMARY KEY,
+                    name VARCHAR(255) NOT NULL,
+                    applied_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP
+                )",
+            )
+            .await?;
+        Ok(())
+    }
+
+    /// Versions recorded in `schema_migrations`, oldest first.
+    pub async fn applied_migrations(&self) -> Result<Vec<i64>, DbError> {
+        self.ensure_migrations_table().await?;
+        let rows = sqlx::query("SELECT version FROM schema_migrations ORDER BY version")
+            .fetch_all(&self.pool)
+            .await?;
+        Ok(rows.iter().map(|row| row.get("version")).collect())
+    }
+
+    /// Applies every pending migration, each in its own transaction.
+    /// Returns the versions that were applied.
+    pub async fn migrate_up(&self) -> Result<Vec<i64>, DbError> {
+        let applied = self.applied_migrations().await?;
+        let mut ran = Vec::new();
+
+        for migration in MIGRATIONS.iter().filter(|m| !applied.contains(&m.version)) {
+            let mut tx = self.pool.begin().await?;
+            tx.execute(migration.up)
+                .await
+                .map_err(|e| DbError::Migration(format!("migration {} ({}) failed: {}", migration.version, migration.name, e)))?;
+            sqlx::query("INSERT INTO schema_migrations (version, name) VALUES ($1, $2)")
+                .bind(migration.version)
+                .bind(migration.name)
+                .execute(&mut *tx)
+                .await?;
+            tx.commit().await?;
+            ran.push(migration.version);
+        }
+
+        Ok(ran)
+    }
+
+    /// Reverts the `steps` most recently applied migrations, newest first.
+    /// Returns the versions that were reverted.
+    pub async fn migrate_down(&self, steps: usize) -> Result<Vec<i64>, DbError> {
+        let applied = self.applied_migrations().await?;
+        let mut reverted = Vec::new();
+
+        for version in applied.iter().rev().take(steps) {
+            let migration = MIGRATIONS
+                .iter()
+                .find(|m| m.version == *version)
+                .ok_or_else(|| DbError::Migration(format!("migration {} is applied but not known to this binary", version)))?;
+            let mut tx = self.pool.begin().await?;
+            tx.execute(migration.down)
+                .await
+                .map_err(|e| DbError::Migration(format!("reverting migration {} ({}) failed: {}", migration.version, migration.name, e)))?;
+            sqlx::query("DELETE FROM schema_migrations WHERE version = $1")
+                .bind(migration.version)
+                .execute(&mut *tx)
+                .await?;
+            tx.commit().await?;
+            reverted.push(migration.version);
+        }
+
+        Ok(reverted)
+    }
+
+    /// Fails when the database is behind or ahead of the migrations built into
+    /// this binary, naming the versions involved.
+    pub async fn check_schema(&self) -> Result<(), DbError> {
+        let applied = self.applied_migrations().await?;
+        let pending: Vec<String> = MIGRATIONS
+            .iter()
+            .filter(|m| !applied.contains(&m.version))
+            .map(|m| m.name.to_string())
+            .collect();
+        if !pending.is_empty() {
+            return Err(DbError::Migration(format!(
+                "database schema is out of date, pending migrations: {} (run `migrate up`)",
+                pending.join(", ")
+            )));
+        }
+
+        let unknown: Vec<String> = applied
+            .iter()
+            .filter(|v| !MIGRATIONS.iter().any(|m| m.version == **v))
+            .map(|v| v.to_string())
+            .collect();
+        if !unknown.is_empty() {
+            return Err(DbError::Migration(format!("database has migrations this binary doesn't know about: {}", unknown.join(", "))));
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Debug, Subcommand)]
+pub enum MigrateCommand {
+    /// Apply every pending migration.
+    Up,
+    /// Revert the most recent migrations.
+    Down {
+        #[arg(default_value_t = 1)]
+        steps: usize,
+    },
+    /// List migrations and whether they have been applied.
+    Status,
+}
+
+/// Handles `migrate up|down [steps]|status` from the command line.
+pub async fn run_migrate_command(command: &MigrateCommand, db: &Database) -> Result<(), Box<dyn Error>> {
+    match command {
+        MigrateCommand::Up => {
+            let ran = db.migrate_up().await?;
+            if ran.is_empty() {
+                println!("Database is up to date");
+            }
+            for version in ran {
+                println!("Applied migration {}", version);
+            }
+        }
+        MigrateCommand::Down { steps } => {
+            for version in db.migrate_down(*steps).await? {
+                println!("Reverted migration {}", version);
+            }
+        }
+        MigrateCommand::Status => {
+            let applied = db.applied_migrations().await?;
+            for migration in MIGRATIONS {
+                let state = if applied.contains(&migration.version) { "applied" } else { "pending" };
+                println!("{:>4}  {:<8} {}", migration.version, state, migration.name);
+            }
+        }
+    }
+
+    Ok(())
+}
@@ -1,4 +1,4 @@
 DROP TABLE results;
 DROP TABLE models;
 DROP TABLE datasets;
-DROP TABLE sessions;
\ No newline at end of file
+DROP TABLE sessions;
@@ -1,5 +1,5 @@
 CREATE TABLE sessions (
-    session_id UUID PRIMARY KEY,
+    session_id VARCHAR(36) PRIMARY KEY,
     session_name VARCHAR NOT NULL,
     created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
     updated_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP
@@ -7,7 +7,7 @@ CREATE TABLE sessions (
 
 CREATE TABLE datasets (
     dataset_id SERIAL PRIMARY KEY,
-    session_id UUID REFERENCES sessions(session_id) ON DELETE CASCADE,
+    session_id VARCHAR(36) NOT NULL REFERENCES sessions(session_id) ON DELETE CASCADE,
     dataset_name VARCHAR NOT NULL,
     data_path VARCHAR NOT NULL,
     created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
@@ -16,22 +16,22 @@ CREATE TABLE datasets (
 
 CREATE TABLE models (
     model_id SERIAL PRIMARY KEY,
-    session_id UUID REFERENCES sessions(session_id) ON DELETE CASCADE,
+    session_id VARCHAR(36) NOT NULL REFERENCES sessions(session_id) ON DELETE CASCADE,
     model_name VARCHAR NOT NULL,
     model_type VARCHAR NOT NULL,
-    model_params JSONB,
+    model_params JSONB NOT NULL DEFAULT '{}',
     created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
     updated_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP
 );
 
 CREATE TABLE results (
     result_id SERIAL PRIMARY KEY,
-    model_id INT REFERENCES models(model_id) ON DELETE CASCADE,
-    session_id UUID REFERENCES sessions(session_id) ON DELETE CASCADE,
-    accuracy FLOAT,
-    precision FLOAT,
-    recall FLOAT,
-    f1_score FLOAT,
+    model_id INT NOT NULL REFERENCES models(model_id) ON DELETE CASCADE,
+    session_id VARCHAR(36) NOT NULL REFERENCES sessions(session_id) ON DELETE CASCADE,
+    accuracy REAL NOT NULL,
+    precision REAL NOT NULL,
+    recall REAL NOT NULL,
+    f1_score REAL NOT NULL,
     created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
     updated_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP
-);
\ No newline at end of file
+);
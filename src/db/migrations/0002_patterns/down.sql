@@ -0,0 +1,3 @@
+DROP TABLE output_patterns;
+DROP TABLE input_patterns;
+DROP FUNCTION update_timestamp();
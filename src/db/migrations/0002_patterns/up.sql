@@ -1,31 +1,40 @@
--- Create the database for LLM Validator
-CREATE DATABASE llm_validator;
+-- Pattern tables read by the input and output validators.
 
--- Connect to the newly created database
-\c llm_validator;
-
--- Create a table for storing input validation patterns
-CREATE TABLE input_validation_patterns (
+CREATE TABLE input_patterns (
     id SERIAL PRIMARY KEY,
-    pattern_name VARCHAR(255) NOT NULL,
-    regex_pattern TEXT NOT NULL,
+    name VARCHAR(255) NOT NULL,
+    pattern TEXT NOT NULL,
     description TEXT,
-    created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
-    updated_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP
 );
 
--- Create a table for storing output validation patterns
-CREATE TABLE output_validation_patterns (
+CREATE TABLE output_patterns (
     id SERIAL PRIMARY KEY,
-    pattern_name VARCHAR(255) NOT NULL,
-    regex_pattern TEXT NOT NULL,
+    name VARCHAR(255) NOT NULL,
+    pattern TEXT NOT NULL,
     description TEXT,
-    created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
-    updated_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP
 );
 
--- Insert initial input validation patterns
-INSERT INTO input_validation_patterns (pattern_name, regex_pattern, description) VALUES
+CREATE OR REPLACE FUNCTION update_timestamp()
+RETURNS TRIGGER AS $$
+BEGIN
+   NEW.updated_at = NOW();
+   RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER update_input_pattern_timestamp
+BEFORE UPDATE ON input_patterns
+FOR EACH ROW EXECUTE FUNCTION update_timestamp();
+
+CREATE TRIGGER update_output_pattern_timestamp
+BEFORE UPDATE ON output_patterns
+FOR EACH ROW EXECUTE FUNCTION update_timestamp();
+
+INSERT INTO input_patterns (name, pattern, description) VALUES
 ('SQL Injection', '(\b(SELECT|INSERT|UPDATE|DELETE|DROP|UNION|ALTER|TRUNCATE|EXEC)\b)', 'Detects SQL injection keywords'),
 ('Command Injection', '(;|\||&|>|<)', 'Detects command injections using shell operators'),
 ('XSS Attack', '(<script\b[^>]*>(.*?)</script>)', 'Detects potential cross-site scripting (XSS) attacks'),
@@ -46,10 +55,9 @@ INSERT INTO input_validation_patterns (pattern_name, regex_pattern, description)
 ('Unicode Encoding Injection', '(\\u[0-9A-Fa-f]{4})', 'Detects Unicode-encoded characters used in injection attempts'),
 ('JavaScript Obfuscation', '(eval\(|Function\(|setTimeout\(|setInterval\()', 'Detects obfuscated JavaScript that could indicate malicious behavior or script injection'),
 ('CSRF Attack', '(<input type="hidden" name="csrf_token" value="[^"]+">)', 'Detects CSRF tokens in forms to ensure that CSRF protection is in place'),
-('Remote File Inclusion', '((http|https|ftp)://.*?(\.php|\.asp|\.jsp|\.pl))')
+('Remote File Inclusion', '((http|https|ftp)://.*?(\.php|\.asp|\.jsp|\.pl))', 'Detects remote file inclusion through URLs to server-side scripts');
 
--- Insert initial output validation patterns
-INSERT INTO output_validation_patterns (pattern_name, regex_pattern, description) VALUES
+INSERT INTO output_patterns (name, pattern, description) VALUES
 ('Sensitive Data', '(\b(password|ssn|credit_card|api_key|secret|token)\b)', 'Detects sensitive data like passwords, SSNs, or API keys in the output'),
 ('Internal Info Leak', '(\b(server_name|host|database|username|env|config)\b)', 'Detects internal server information, environment variables, or configurations leaking through output'),
 ('Prompt Injection', '(\b(prompt|input|instruction|generate|complete)\b)', 'Detects prompt injection keywords or malicious prompt alterations in AI output'),
@@ -70,43 +78,8 @@ INSERT INTO output_validation_patterns (pattern_name, regex_pattern, description
 ('Social Media Tokens', '(\b([A-Za-z0-9]{39,})\b)', 'Detects tokens from social media platforms (like Facebook, Twitter, etc.) in the output'),
 ('JSON Structure', '(\{[^}]*:.*?\})', 'Detects possible JSON object structures in output, useful for identifying data leaks in JSON responses'),
 ('HTML Comment Disclosure', '(<\!--[^>]*-->)', 'Detects HTML comments that may contain sensitive information or internal debugging data'),
-('Environment Variable Leak', '(\$[A-Z_][A-Z0-9_]*(?=\s*=))', 'Detects environment variables that may have been exposed in the output'),
+('Environment Variable Leak', '(\$[A-Z_][A-Z0-9_]*\s*=)', 'Detects environment variables that may have been exposed in the output'),
 ('Version Information Disclosure', '(\b(version|v\d+\.\d+\.\d+)\b)', 'Detects software version information leaking, which may help attackers identify vulnerable software versions'),
 ('Phone Number Leakage', '(\b\+?[0-9]{1,3}[\s\-\.]?[0-9]{1,4}[\s\-\.]?[0-9]{1,4}[\s\-\.]?[0-9]{1,9}\b)', 'Detects phone numbers that could expose private user information'),
 ('External URL Exposure', '((https?|ftp):\/\/[^\s\/$.?#].[^\s]*)', 'Detects URLs that point to external domains, useful for ensuring no sensitive information is leaked to external servers'),
 ('CRLF Injection in Output', '(%0D%0A|\r\n|\n|\r)', 'Detects potential CRLF injection sequences in output that could be used to inject headers or manipulate logs');
-
--- Add triggers for updating the timestamp on updates
-CREATE OR REPLACE FUNCTION update_timestamp()
-RETURNS TRIGGER AS $$
-BEGIN
-   NEW.updated_at = NOW();
-   RETURN NEW;
-END;
-$$ LANGUAGE plpgsql;
-
--- Trigger for updating input validation patterns
-CREATE TRIGGER update_input_pattern_timestamp
-BEFORE UPDATE ON input_validation_patterns
-FOR EACH ROW EXECUTE FUNCTION update_timestamp();
-
--- Trigger for updating output validation patterns
-CREATE TRIGGER update_output_pattern_timestamp
-BEFORE UPDATE ON output_validation_patterns
-FOR EACH ROW EXECUTE FUNCTION update_timestamp();
-
--- New Validation
-CREATE TABLE input_validations (
-    id SERIAL PRIMARY KEY,
-    text TEXT NOT NULL,
-    prompt_injection BOOLEAN,
-    created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP
-);
-
-CREATE TABLE output_validations (
-    id SERIAL PRIMARY KEY,
-    text TEXT NOT NULL,
-    sentiment VARCHAR(50),
-    prompt_injection BOOLEAN,
-    created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP
-);
@@ -0,0 +1,4 @@
+DROP TRIGGER output_patterns_history ON output_patterns;
+DROP TRIGGER input_patterns_history ON input_patterns;
+DROP FUNCTION record_pattern_change();
+DROP TABLE pattern_history;
@@ -0,0 +1,65 @@
+-- Pattern history for input_patterns / output_patterns.
+--
+-- Every INSERT, UPDATE and DELETE on the pattern tables is copied into
+-- pattern_history by a trigger, so edits made straight from psql are recorded
+-- as well as the ones made by the validator. The author and reason are read
+-- from the transaction-local settings `llm_validator.author` and
+-- `llm_validator.reason`; without them the author falls back to the database
+-- user.
+
+CREATE TABLE pattern_history (
+    version BIGSERIAL PRIMARY KEY,
+    pattern_table VARCHAR(32) NOT NULL,
+    pattern_id INT NOT NULL,
+    operation VARCHAR(6) NOT NULL,
+    name VARCHAR(255) NOT NULL,
+    pattern TEXT NOT NULL,
+    description TEXT,
+    author TEXT NOT NULL,
+    reason TEXT,
+    changed_at TIMESTAMP NOT NULL DEFAULT clock_timestamp()
+);
+
+CREATE INDEX pattern_history_lookup
+    ON pattern_history (pattern_table, pattern_id, changed_at);
+
+CREATE OR REPLACE FUNCTION record_pattern_change()
+RETURNS TRIGGER AS $$
+DECLARE
+    row_state RECORD;
+BEGIN
+    IF TG_OP = 'DELETE' THEN
+        row_state := OLD;
+    ELSE
+        row_state := NEW;
+    END IF;
+
+    INSERT INTO pattern_history (pattern_table, pattern_id, operation, name, pattern, description, author, reason)
+    VALUES (
+        TG_TABLE_NAME,
+        row_state.id,
+        TG_OP,
+        row_state.name,
+        row_state.pattern,
+        row_state.description,
+        COALESCE(NULLIF(current_setting('llm_validator.author', true), ''), session_user),
+        NULLIF(current_setting('llm_validator.reason', true), '')
+    );
+
+    RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER input_patterns_history
+AFTER INSERT OR UPDATE OR DELETE ON input_patterns
+FOR EACH ROW EXECUTE FUNCTION record_pattern_change();
+
+CREATE TRIGGER output_patterns_history
+AFTER INSERT OR UPDATE OR DELETE ON output_patterns
+FOR EACH ROW EXECUTE FUNCTION record_pattern_change();
+
+-- Rows seeded before this migration start the history as plain inserts.
+INSERT INTO pattern_history (pattern_table, pattern_id, operation, name, pattern, description, author, reason, changed_at)
+SELECT 'input_patterns', id, 'INSERT', name, pattern, description, 'migration', 'initial rule set', created_at FROM input_patterns
+UNION ALL
+SELECT 'output_patterns', id, 'INSERT', name, pattern, description, 'migration', 'initial rule set', created_at FROM output_patterns;
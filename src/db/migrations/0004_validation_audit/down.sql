@@ -0,0 +1,2 @@
+DROP TABLE validation_findings;
+DROP TABLE validation_runs;
@@ -0,0 +1,31 @@
+-- Audit trail of validation decisions.
+--
+-- One validation_runs row per validated message, plus one validation_findings
+-- row per rule that matched it. Rows are written in batches by the audit
+-- writer, which generates run_uuid itself so findings can be inserted in the
+-- same batch as their run.
+
+CREATE TABLE validation_runs (
+    id SERIAL PRIMARY KEY,
+    run_uuid VARCHAR(36) NOT NULL UNIQUE,
+    validation_type VARCHAR(16) NOT NULL,
+    run_time TIMESTAMP NOT NULL,
+    status VARCHAR(16) NOT NULL,
+    errors_found INT NOT NULL DEFAULT 0,
+    duration_ms BIGINT NOT NULL DEFAULT 0
+);
+
+CREATE INDEX validation_runs_time ON validation_runs (run_time);
+
+CREATE TABLE validation_findings (
+    id SERIAL PRIMARY KEY,
+    run_uuid VARCHAR(36) NOT NULL REFERENCES validation_runs(run_uuid) ON DELETE CASCADE,
+    rule VARCHAR(255) NOT NULL,
+    description TEXT,
+    direction VARCHAR(16) NOT NULL,
+    span_start INT NOT NULL,
+    span_end INT NOT NULL
+);
+
+CREATE INDEX validation_findings_run ON validation_findings (run_uuid);
+CREATE INDEX validation_findings_rule ON validation_findings (rule);
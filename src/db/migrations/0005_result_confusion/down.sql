@@ -0,0 +1,6 @@
+ALTER TABLE results
+    DROP COLUMN false_negatives,
+    DROP COLUMN true_negatives,
+    DROP COLUMN false_positives,
+    DROP COLUMN true_positives,
+    DROP COLUMN dataset_id;
@@ -0,0 +1,11 @@
+-- Evaluation results record which dataset they were measured on and the
+-- confusion matrix behind the four scores.
+
+ALTER TABLE results
+    ADD COLUMN dataset_id INT REFERENCES datasets(dataset_id) ON DELETE CASCADE,
+    ADD COLUMN true_positives INT NOT NULL DEFAULT 0,
+    ADD COLUMN false_positives INT NOT NULL DEFAULT 0,
+    ADD COLUMN true_negatives INT NOT NULL DEFAULT 0,
+    ADD COLUMN false_negatives INT NOT NULL DEFAULT 0;
+
+CREATE INDEX results_dataset ON results (dataset_id);
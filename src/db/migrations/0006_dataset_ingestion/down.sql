@@ -0,0 +1,6 @@
+DROP INDEX datasets_content_sha256;
+
+ALTER TABLE datasets
+    DROP COLUMN example_count,
+    DROP COLUMN content_sha256,
+    DROP COLUMN format;
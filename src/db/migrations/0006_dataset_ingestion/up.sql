@@ -0,0 +1,9 @@
+-- Imported datasets are stored as normalized JSONL; the hash of that file is
+-- checked every time the dataset is read.
+
+ALTER TABLE datasets
+    ADD COLUMN format VARCHAR(16) NOT NULL DEFAULT 'jsonl',
+    ADD COLUMN content_sha256 VARCHAR(64),
+    ADD COLUMN example_count INT NOT NULL DEFAULT 0;
+
+CREATE UNIQUE INDEX datasets_content_sha256 ON datasets (content_sha256);
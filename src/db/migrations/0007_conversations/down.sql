@@ -0,0 +1,5 @@
+DROP TABLE conversation_turns;
+
+ALTER TABLE sessions
+    DROP COLUMN turn_count,
+    DROP COLUMN risk_score;
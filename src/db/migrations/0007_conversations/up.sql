@@ -0,0 +1,18 @@
+-- Conversation state for multi-turn validation. A conversation is a session;
+-- the validator keeps its rolling window of recent turns and its running risk
+-- score here so they survive restarts and are shared between instances.
+
+ALTER TABLE sessions
+    ADD COLUMN risk_score REAL NOT NULL DEFAULT 0,
+    ADD COLUMN turn_count INT NOT NULL DEFAULT 0;
+
+CREATE TABLE conversation_turns (
+    id SERIAL PRIMARY KEY,
+    session_id VARCHAR(36) NOT NULL REFERENCES sessions(session_id) ON DELETE CASCADE,
+    turn INT NOT NULL,
+    direction VARCHAR(16) NOT NULL,
+    content TEXT NOT NULL,
+    blocked BOOLEAN NOT NULL
+);
+
+CREATE INDEX conversation_turns_session ON conversation_turns (session_id, turn);
@@ -0,0 +1,15 @@
+-- Per-client reputation. One row per user or client that has tripped a
+-- detector, holding its risk as of updated_at (the validator decays it from
+-- there) and the end of any block in force. Written in batches by the
+-- reputation tracker so state survives restarts and is shared between
+-- instances.
+
+CREATE TABLE client_reputation (
+    client_id VARCHAR(255) PRIMARY KEY,
+    risk DOUBLE PRECISION NOT NULL DEFAULT 0,
+    updated_at TIMESTAMP NOT NULL,
+    blocked_until TIMESTAMP,
+    offenses BIGINT NOT NULL DEFAULT 0
+);
+
+CREATE INDEX client_reputation_blocked ON client_reputation (blocked_until) WHERE blocked_until IS NOT NULL;
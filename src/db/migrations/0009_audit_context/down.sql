@@ -0,0 +1,3 @@
+DROP INDEX validation_runs_request;
+ALTER TABLE validation_runs DROP COLUMN app;
+ALTER TABLE validation_runs DROP COLUMN request_id;
@@ -0,0 +1,7 @@
+-- Ties each audited run to the request it answered and the app it came from,
+-- so a run can be found from a log line or alert and traced to its client.
+
+ALTER TABLE validation_runs ADD COLUMN request_id VARCHAR(255);
+ALTER TABLE validation_runs ADD COLUMN app VARCHAR(255);
+
+CREATE INDEX validation_runs_request ON validation_runs (request_id);
@@ -0,0 +1,11 @@
+pub mod audit;
+pub mod db;
+pub mod error;
+pub mod experiments;
+pub mod migrations;
+pub mod pattern_history;
+pub mod reputation;
+pub mod store;
+
+pub use self::db::Database;
+pub use self::error::DbError;
@@ -0,0 +1,293 @@
+use chrono::NaiveDateTime;
+use clap::Subcommand;
+use regex::Regex;
+use sqlx::postgres::{PgRow, Postgres};
+use sqlx::{Row, Transaction};
+use std::collections::HashMap;
+use std::error::Error;
+
+use crate::db::error::DbError;
+use crate::db::Database;
+pub use crate::db::store::PatternKind;
+
+/// Who made a change and why. Stored alongside every history row.
+#[derive(Debug, Clone)]
+pub struct PatternChange {
+    pub author: String,
+    pub reason: String,
+}
+
+/// One row of `pattern_history`.
+#[derive(Debug, Clone)]
+pub struct PatternVersion {
+    pub version: i64,
+    pub pattern_id: i32,
+    pub operation: String,
+    pub name: String,
+    pub pattern: String,
+    pub description: Option<String>,
+    pub author: String,
+    pub reason: Option<String>,
+    pub changed_at: NaiveDateTime,
+}
+
+/// A pattern as it existed at some point in time.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PatternSnapshot {
+    pub pattern_id: i32,
+    pub name: String,
+    pub pattern: String,
+    pub description: String,
+}
+
+/// Sets the transaction-local author and reason picked up by the
+/// `record_pattern_change` trigger.
+async fn tag_transaction(tx: &mut Transaction<'_, Postgres>, change: &PatternChange) -> Result<(), DbError> {
+    sqlx::query("SELECT set_config('llm_validator.author', $1, true), set_config('llm_validator.reason', $2, true)")
+        .bind(&change.author)
+        .bind(&change.reason)
+        .execute(&mut *tx)
+        .await?;
+    Ok(())
+}
+
+fn snapshot_from_row(row: &PgRow) -> PatternSnapshot {
+    let description: Option<String> = row.get("description");
+    PatternSnapshot {
+        pattern_id: row.get("pattern_id"),
+        name: row.get("name"),
+        pattern: row.get("pattern"),
+        description: description.unwrap_or_default(),
+    }
+}
+
+impl Database {
+    /// Adds a pattern and records the change. Returns the id of the new row.
+    pub async fn insert_pattern(&self, kind: PatternKind, name: &str, pattern: &str, description: &str, change: &PatternChange) -> Result<i32, DbError> {
+        Regex::new(pattern)?;
+
+        let mut tx = self.pool.begin().await?;
+        tag_transaction(&mut tx, change).await?;
+        let row = sqlx::query(&format!("INSERT INTO {} (name, pattern, description) VALUES ($1, $2, $3) RETURNING id", kind.table_name()))
+            .bind(name)
+            .bind(pattern)
+            .bind(description)
+            .fetch_one(&mut *tx)
+            .await?;
+        tx.commit().await?;
+
+        Ok(row.get("id"))
+    }
+
+    /// Replaces the pattern and description of an existing row.
+    pub async fn update_pattern(&self, kind: PatternKind, id: i32, pattern: &str, description: &str, change: &PatternChange) -> Result<(), DbError> {
+        Regex::new(pattern)?;
+
+        let mut tx = self.pool.begin().await?;
+        tag_transaction(&mut tx, change).await?;
+        sqlx::query(&format!("UPDATE {} SET pattern = $1, description = $2 WHERE id = $3", kind.table_name()))
+            .bind(pattern)
+            .bind(description)
+            .bind(id)
+            .execute(&mut *tx)
+            .await?;
+        tx.commit().await?;
+
+        Ok(())
+    }
+
+    pub async fn delete_pattern(&self, kind: PatternKind, id: i32, change: &PatternChange) -> Result<(), DbError> {
+        let mut tx = self.pool.begin().await?;
+        tag_transaction(&mut tx, change).await?;
+        sqlx::query(&format!("DELETE FROM {} WHERE id = $1", kind.table_name()))
+            .bind(id)
+            .execute(&mut *tx)
+            .await?;
+        tx.commit().await?;
+
+        Ok(())
+    }
+
+    /// Most recent changes first.
+    pub async fn pattern_history(&self, kind: PatternKind, limit: i64) -> Result<Vec<PatternVersion>, DbError> {
+        let rows = sqlx::query(
+            "SELECT version, pattern_id, operation, name, pattern, description, author, reason, changed_at
+             FROM pattern_history WHERE pattern_table = $1
+             ORDER BY version DESC LIMIT $2",
+        )
+        .bind(kind.table_name())
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows
+            .iter()
+            .map(|row| PatternVersion {
+                version: row.get("version"),
+                pattern_id: row.get("pattern_id"),
+                operation: row.get("operation"),
+                name: row.get("name"),
+                pattern: row.get("pattern"),
+                description: row.get("description"),
+                author: row.get("author"),
+                reason: row.get("reason"),
+                changed_at: row.get("changed_at"),
+            })
+            .collect())
+    }
+
+    /// Rebuilds the rule set as it was at `at` from the history table.
+    pub async fn patterns_as_of(&self, kind: PatternKind, at: NaiveDateTime) -> Result<Vec<PatternSnapshot>, DbError> {
+        let rows = sqlx::query(
+            "SELECT DISTINCT ON (pattern_id) pattern_id, operation, name, pattern, description
+             FROM pattern_history WHERE pattern_table = $1 AND changed_at <= $2
+             ORDER BY pattern_id, version DESC",
+        )
+        .bind(kind.table_name())
+        .bind(at)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows
+            .iter()
+            .filter(|row| row.get::<String, _>("operation") != "DELETE")
+            .map(snapshot_from_row)
+            .collect())
+    }
+
+    /// Restores the rule set to how it looked at `at`. The rollback itself is
+    /// recorded in the history like any other change, so it can be undone.
+    /// Returns the number of rows that were inserted, updated or deleted.
+    pub async fn rollback_patterns(&self, kind: PatternKind, at: NaiveDateTime, change: &PatternChange) -> Result<usize, DbError> {
+        let target: HashMap<i32, PatternSnapshot> = self
+            .patterns_as_of(kind, at)
+            .await?
+            .into_iter()
+            .map(|p| (p.pattern_id, p))
+            .collect();
+        let table = kind.table_name();
+
+        let mut tx = self.pool.begin().await?;
+        tag_transaction(&mut tx, change).await?;
+
+        let current: HashMap<i32, PatternSnapshot> = sqlx::query(&format!("SELECT id AS pattern_id, name, pattern, description FROM {} FOR UPDATE", table))
+            .fetch_all(&mut *tx)
+            .await?
+            .iter()
+            .map(|row| {
+                let p = snapshot_from_row(row);
+                (p.pattern_id, p)
+            })
+            .collect();
+
+        let mut changed = 0;
+        for id in current.keys().filter(|id| !target.contains_key(id)) {
+            sqlx::query(&format!("DELETE FROM {} WHERE id = $1", table))
+                .bind(id)
+                .execute(&mut *tx)
+                .await?;
+            changed += 1;
+        }
+        for (id, wanted) in &target {
+            match current.get(id) {
+                Some(existing) if existing == wanted => {}
+                Some(_) => {
+                    sqlx::query(&format!("UPDATE {} SET name = $1, pattern = $2, description = $3 WHERE id = $4", table))
+                        .bind(&wanted.name)
+                        .bind(&wanted.pattern)
+                        .bind(&wanted.description)
+                        .bind(id)
+                        .execute(&mut *tx)
+                        .await?;
+                    changed += 1;
+                }
+                None => {
+                    sqlx::query(&format!("INSERT INTO {} (id, name, pattern, description) VALUES ($1, $2, $3, $4)", table))
+                        .bind(id)
+                        .bind(&wanted.name)
+                        .bind(&wanted.pattern)
+                        .bind(&wanted.description)
+                        .execute(&mut *tx)
+                        .await?;
+                    changed += 1;
+                }
+            }
+        }
+
+        tx.commit().await?;
+        Ok(changed)
+    }
+}
+
+fn parse_kind(value: &str) -> Result<PatternKind, String> {
+    PatternKind::parse(value).ok_or_else(|| format!("expected input or output, got '{}'", value))
+}
+
+fn parse_time(value: &str) -> Result<NaiveDateTime, String> {
+    NaiveDateTime::parse_from_str(value, "%Y-%m-%d %H:%M:%S").map_err(|e| format!("expected YYYY-MM-DD HH:MM:SS: {}", e))
+}
+
+#[derive(Debug, Subcommand)]
+pub enum PatternsCommand {
+    /// Show the most recent changes to a pattern table.
+    History {
+        #[arg(value_parser = parse_kind)]
+        kind: PatternKind,
+        #[arg(default_value_t = 50)]
+        limit: i64,
+    },
+    /// Show a pattern table as it was at a point in time.
+    AsOf {
+        #[arg(value_parser = parse_kind)]
+        kind: PatternKind,
+        /// YYYY-MM-DD HH:MM:SS
+        #[arg(value_parser = parse_time)]
+        at: NaiveDateTime,
+    },
+    /// Restore a pattern table to how it was at a point in time.
+    Rollback {
+        #[arg(value_parser = parse_kind)]
+        kind: PatternKind,
+        /// YYYY-MM-DD HH:MM:SS
+        #[arg(value_parser = parse_time)]
+        at: NaiveDateTime,
+        /// Recorded in the history alongside the change.
+        reason: String,
+    },
+}
+
+/// Handles `patterns history|as-of|rollback ...` from the command line.
+pub async fn run_patterns_command(command: &PatternsCommand, db: &Database) -> Result<(), Box<dyn Error>> {
+    match command {
+        PatternsCommand::History { kind, limit } => {
+            for v in db.pattern_history(*kind, *limit).await? {
+                println!(
+                    "#{} {} {} id={} by {}: {} {} ({})",
+                    v.version,
+                    v.changed_at,
+                    v.operation,
+                    v.pattern_id,
+                    v.author,
+                    v.name,
+                    v.pattern,
+                    v.reason.unwrap_or_default()
+                );
+            }
+        }
+        PatternsCommand::AsOf { kind, at } => {
+            for p in db.patterns_as_of(*kind, *at).await? {
+                println!("{}\t{}\t{}\t{}", p.pattern_id, p.name, p.pattern, p.description);
+            }
+        }
+        PatternsCommand::Rollback { kind, at, reason } => {
+            let change = PatternChange {
+                author: std::env::var("USER").unwrap_or_else(|_| "unknown".to_string()),
+                reason: format!("rollback to {}: {}", at, reason),
+            };
+            let changed = db.rollback_patterns(*kind, *at, &change).await?;
+            println!("Rolled back {} to {} ({} rows changed)", kind.table_name(), at, changed);
+        }
+    }
+
+    Ok(())
+}
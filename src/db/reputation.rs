@@ -0,0 +1,125 @@
+use chrono::{NaiveDateTime, TimeZone, Utc};
+use clap::Subcommand;
+use sqlx::postgres::PgRow;
+use sqlx::Row;
+use std::error::Error;
+
+use crate::db::error::DbError;
+use crate::db::Database;
+use crate::reputation::ClientReputation;
+
+fn reputation_from_row(row: &PgRow) -> ClientReputation {
+    let updated_at: NaiveDateTime = row.get("updated_at");
+    let blocked_until: Option<NaiveDateTime> = row.get("blocked_until");
+    let offenses: i64 = row.get("offenses");
+    ClientReputation {
+        client_id: row.get("client_id"),
+        risk: row.get("risk"),
+        updated_at: Utc.from_utc_datetime(&updated_at),
+        blocked_until: blocked_until.map(|t| Utc.from_utc_datetime(&t)),
+        offenses: offenses.max(0) as u64,
+    }
+}
+
+impl Database {
+    /// Every saved client, riskiest as of its last update first.
+    pub async fn fetch_reputations(&self) -> Result<Vec<ClientReputation>, DbError> {
+        let rows = sqlx::query("SELECT client_id, risk, updated_at, blocked_until, offenses FROM client_reputation ORDER BY risk DESC")
+            .fetch_all(&self.pool)
+            .await?;
+        Ok(rows.iter().map(reputation_from_row).collect())
+    }
+
+    pub async fn fetch_reputation(&self, client_id: &str) -> Result<Option<ClientReputation>, DbError> {
+        let row = sqlx::query("SELECT client_id, risk, updated_at, blocked_until, offenses FROM client_reputation WHERE client_id = $1")
+            .bind(client_id)
+            .fetch_optional(&self.pool)
+            .await?;
+        Ok(row.as_ref().map(reputation_from_row))
+    }
+
+    /// Inserts or replaces the rows of `clients` in one transaction.
+    pub async fn save_reputations(&self, clients: &[ClientReputation]) -> Result<(), DbError> {
+        if clients.is_empty() {
+            return Ok(());
+        }
+        let mut tx = self.pool.begin().await?;
+        for client in clients {
+            sqlx::query(
+                "INSERT INTO client_reputation (client_id, risk, updated_at, blocked_until, offenses) VALUES ($1, $2, $3, $4, $5)
+                 ON CONFLICT (client_id) DO UPDATE SET risk = $2, updated_at = $3, blocked_until = $4, offenses = $5",
+            )
+            .bind(&client.client_id)
+            .bind(client.risk)
+            .bind(client.updated_at.naive_utc())
+            .bind(client.blocked_until.map(|t| t.naive_utc()))
+            .bind(client.offenses as i64)
+            .execute(&mut *tx)
+            .await?;
+        }
+        tx.commit().await?;
+        Ok(())
+    }
+
+    /// Returns whether the client had a row.
+    pub async fn delete_reputation(&self, client_id: &str) -> Result<bool, DbError> {
+        let result = sqlx::query("DELETE FROM client_reputation WHERE client_id = $1")
+            .bind(client_id)
+            .execute(&self.pool)
+            .await?;
+        Ok(result.rows_affected() > 0)
+    }
+}
+
+#[derive(Debug, Subcommand)]
+pub enum ReputationCommand {
+    /// Show the saved clients, riskiest first.
+    List {
+        #[arg(default_value_t = 50)]
+        limit: usize,
+    },
+    /// Show one client's saved state.
+    Show { client_id: String },
+    /// Forget a client, lifting any throttle or block. Running validators
+    /// keep their own copy until they restart; reset those over their HTTP
+    /// API.
+    Reset { client_id: String },
+}
+
+fn print_reputation(client: &ClientReputation) {
+    let blocked = match client.blocked_until {
+        Some(until) if until > Utc::now() => format!("blocked until {}", until.format("%Y-%m-%d %H:%M:%S")),
+        _ => "not blocked".to_string(),
+    };
+    println!(
+        "{}\trisk {:.2} as of {}\t{} offenses\t{}",
+        client.client_id,
+        client.risk,
+        client.updated_at.format("%Y-%m-%d %H:%M:%S"),
+        client.offenses,
+        blocked
+    );
+}
+
+/// Handles `reputation list|show|reset ...` from the command line.
+pub async fn run_reputation_command(command: &ReputationCommand, db: &Database) -> Result<(), Box<dyn Error>> {
+    match command {
+        ReputationCommand::List { limit } => {
+            for client in db.fetch_reputations().await?.iter().take(*limit) {
+                print_reputation(client);
+            }
+        }
+        ReputationCommand::Show { client_id } => match db.fetch_reputation(client_id).await? {
+            Some(client) => print_reputation(&client),
+            None => return Err(DbError::NotFound(format!("client '{}'", client_id)).into()),
+        },
+        ReputationCommand::Reset { client_id } => {
+            if !db.delete_reputation(client_id).await? {
+                return Err(DbError::NotFound(format!("client '{}'", client_id)).into());
+            }
+            println!("Reset {}", client_id);
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,219 @@
+use async_trait::async_trait;
+use regex::Regex;
+use sqlx::sqlite::{SqliteConnectOptions, SqlitePool};
+use sqlx::{Executor, Row};
+use std::error::Error;
+use std::sync::{Arc, RwLock};
+
+use crate::config::Config;
+use crate::db::Database;
+
+pub type StoreError = Box<dyn Error + Send + Sync>;
+
+/// Which of the two pattern tables a change applies to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PatternKind {
+    Input,
+    Output,
+}
+
+impl PatternKind {
+    pub fn table_name(&self) -> &'static str {
+        match self {
+            PatternKind::Input => "input_patterns",
+            PatternKind::Output => "output_patterns",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<PatternKind> {
+        match value.to_ascii_lowercase().as_str() {
+            "input" | "input_patterns" => Some(PatternKind::Input),
+            "output" | "output_patterns" => Some(PatternKind::Output),
+            _ => None,
+        }
+    }
+}
+
+/// A compiled validation pattern.
+#[derive(Debug, Clone)]
+pub struct Pattern {
+    pub name: String,
+    pub regex: Regex,
+    pub description: String,
+}
+
+impl Pattern {
+    pub fn new(name: &str, pattern: &str, description: &str) -> Result<Pattern, StoreError> {
+        Ok(Pattern {
+            name: name.to_string(),
+            regex: Regex::new(pattern)?,
+            description: description.to_string(),
+        })
+    }
+}
+
+/// Source of the patterns the validators run. The validators only ever see
+/// this trait, so the backing database can be swapped per deployment.
+#[async_trait]
+pub trait PatternStore: Send + Sync {
+    async fn fetch_patterns(&self, kind: PatternKind) -> Result<Vec<Pattern>, StoreError>;
+
+    async fn fetch_input_patterns(&self) -> Result<Vec<Pattern>, StoreError> {
+        self.fetch_patterns(PatternKind::Input).await
+    }
+
+    async fn fetch_output_patterns(&self) -> Result<Vec<Pattern>, StoreError> {
+        self.fetch_patterns(PatternKind::Output).await
+    }
+}
+
+#[async_trait]
+impl PatternStore for Database {
+    async fn fetch_patterns(&self, kind: PatternKind) -> Result<Vec<Pattern>, StoreError> {
+        let rows = sqlx::query(&format!("SELECT name, pattern, description FROM {} ORDER BY id", kind.table_name()))
+            .fetch_all(&self.pool)
+            .await?;
+
+        let mut patterns = Vec::new();
+        for row in rows {
+            let description: Option<String> = row.get("description");
+            patterns.push(Pattern::new(row.get("name"), row.get("pattern"), &description.unwrap_or_default())?);
+        }
+        Ok(patterns)
+    }
+}
+
+/// Patterns kept in a single SQLite file, for edge deployments without a
+/// database server. The tables are created on first use.
+pub struct SqlitePatternStore {
+    pool: SqlitePool,
+}
+
+impl SqlitePatternStore {
+    pub async fn open(path: &str) -> Result<Self, StoreError> {
+        let options = SqliteConnectOptions::new().filename(path).create_if_missing(true);
+        let pool = SqlitePool::connect_with(options).await?;
+
+        for kind in [PatternKind::Input, PatternKind::Output] {
+            pool.execute(
+                format!(
+                    "CREATE TABLE IF NOT EXISTS {} (
+                        id INTEGER PRIMARY KEY AUTOINCREMENT,
+                        name TEXT NOT NULL,
+                        pattern TEXT NOT NULL,
+                        description TEXT
+                    )",
+                    kind.table_name()
+                )
+                .as_str(),
+            )
+            .await?;
+        }
+
+        Ok(SqlitePatternStore { pool })
+    }
+
+    pub async fn add_pattern(&self, kind: PatternKind, name: &str, pattern: &str, description: &str) -> Result<(), StoreError> {
+        Regex::new(pattern)?;
+        sqlx::query(&format!("INSERT INTO {} (name, pattern, description) VALUES (?, ?, ?)", kind.table_name()))
+            .bind(name)
+            .bind(pattern)
+            .bind(description)
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl PatternStore for SqlitePatternStore {
+    async fn fetch_patterns(&self, kind: PatternKind) -> Result<Vec<Pattern>, StoreError> {
+        let rows = sqlx::query(&format!("SELECT name, pattern, description FROM {} ORDER BY id", kind.table_name()))
+            .fetch_all(&self.pool)
+            .await?;
+
+        let mut patterns = Vec::new();
+        for row in rows {
+            let description: Option<String> = row.get("description");
+            patterns.push(Pattern::new(row.get("name"), row.get("pattern"), &description.unwrap_or_default())?);
+        }
+        Ok(patterns)
+    }
+}
+
+/// Patterns held in memory. Used by tests and for running on a laptop or in
+/// CI without any database.
+#[derive(Default)]
+pub struct MemoryPatternStore {
+    input: RwLock<Vec<Pattern>>,
+    output: RwLock<Vec<Pattern>>,
+}
+
+impl MemoryPatternStore {
+    pub fn new() -> Self {
+        MemoryPatternStore::default()
+    }
+
+    /// A store holding the built-in patterns that used to be hardcoded in
+    /// `main.rs`.
+    pub fn with_defaults() -> Self {
+        let store = MemoryPatternStore::new();
+        store
+            .add(
+                PatternKind::Input,
+                "SQL Injection",
+                r"(\b(SELECT|INSERT|UPDATE|DELETE|DROP|UNION|ALTER|TRUNCATE|EXEC)\b)",
+                "Detects SQL injection keywords",
+            )
+            .expect("built-in pattern");
+        store
+            .add(PatternKind::Input, "Command Injection", r"(;|\||&|>|<)", "Detects command injections using shell operators")
+            .expect("built-in pattern");
+        store
+            .add(PatternKind::Output, "Sensitive Data Leak", r"\b(api_key|password)\b", "Detects sensitive information leaks")
+            .expect("built-in pattern");
+        store
+    }
+
+    pub fn add(&self, kind: PatternKind, name: &str, pattern: &str, description: &str) -> Result<(), StoreError> {
+        let pattern = Pattern::new(name, pattern, description)?;
+        self.patterns(kind).write().unwrap().push(pattern);
+        Ok(())
+    }
+
+    fn patterns(&self, kind: PatternKind) -> &RwLock<Vec<Pattern>> {
+        match kind {
+            PatternKind::Input => &self.input,
+            PatternKind::Output => &self.output,
+        }
+    }
+}
+
+#[async_trait]
+impl PatternStore for MemoryPatternStore {
+    async fn fetch_patterns(&self, kind: PatternKind) -> Result<Vec<Pattern>, StoreError> {
+        Ok(self.patterns(kind).read().unwrap().clone())
+    }
+}
+
+/// Opens the store selected by `patterns.backend`. The Postgres store shares
+/// `db` when the command already has a connection, and otherwise connects
+/// with the `database` settings.
+pub async fn open_store(config: &Config, db: Option<&Database>) -> Result<Arc<dyn PatternStore>, Box<dyn Error>> {
+    match config.patterns.backend.as_str() {
+        "postgres" => match db {
+            Some(db) => Ok(Arc::new(db.clone())),
+            None => {
+                let db = Database::from_config(&config.database).await?;
+                db.check_schema().await?;
+                Ok(Arc::new(db))
+            }
+        },
+        "sqlite" => {
+            let store = SqlitePatternStore::open(&config.patterns.sqlite_path).await.map_err(|e| e.to_string())?;
+            Ok(Arc::new(store))
+        }
+        "memory" => Ok(Arc::new(MemoryPatternStore::with_defaults())),
+        other => Err(format!("unknown pattern store '{}', expected postgres, sqlite or memory", other).into()),
+    }
+}
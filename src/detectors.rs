@@ -0,0 +1,142 @@
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::error::Error;
+use std::sync::OnceLock;
+
+use crate::db::store::{Pattern, PatternKind, PatternStore};
+use crate::findings::{Direction, Finding};
+use crate::nlp_analysis::PROMPT_INJECTION_PHRASES;
+
+/// Something that inspects a message and reports what it found. Detectors are
+/// plain synchronous code so they can run on any thread; anything they need
+/// from the database is loaded up front.
+pub trait Detector: Send + Sync {
+    fn name(&self) -> &str;
+
+    /// Which direction this detector looks at.
+    fn applies_to(&self, direction: Direction) -> bool;
+
+    fn detect(&self, text: &str, direction: Direction) -> Vec<Finding>;
+
+    /// Risk score in `[0, 1]` of what `detect` found, compared against the
+    /// detector's threshold. By default each finding halves the remaining
+    /// distance to 1, so one finding scores 0.5, two score 0.75 and so on.
+    fn score_findings(&self, findings: &[Finding]) -> f32 {
+        1.0 - 0.5f32.powi(findings.len() as i32)
+    }
+
+    fn score(&self, text: &str, direction: Direction) -> f32 {
+        self.score_findings(&self.detect(text, direction))
+    }
+}
+
+/// Runs a snapshot of the stored regex patterns for one direction.
+pub struct PatternDetector {
+    name: String,
+    direction: Direction,
+    patterns: Vec<Pattern>,
+}
+
+impl PatternDetector {
+    pub fn new(name: &str, direction: Direction, patterns: Vec<Pattern>) -> Self {
+        PatternDetector {
+            name: name.to_string(),
+            direction,
+            patterns,
+        }
+    }
+}
+
+impl Detector for PatternDetector {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn applies_to(&self, direction: Direction) -> bool {
+        direction == self.direction
+    }
+
+    fn detect(&self, text: &str, direction: Direction) -> Vec<Finding> {
+        self.patterns
+            .iter()
+            .filter_map(|p| p.regex.find(text).map(|m| Finding::new(&p.name, &p.description, direction, m.start(), m.end())))
+            .collect()
+    }
+}
+
+/// Looks for the known prompt injection phrases, ignoring case.
+pub struct PromptInjectionDetector;
+
+impl Detector for PromptInjectionDetector {
+    fn name(&self) -> &str {
+        "prompt_injection"
+    }
+
+    fn applies_to(&self, _direction: Direction) -> bool {
+        true
+    }
+
+    fn detect(&self, text: &str, direction: Direction) -> Vec<Finding> {
+        let mut seen = vec![false; PROMPT_INJECTION_PHRASES.len()];
+        let mut findings = Vec::new();
+        for captures in injection_phrases().captures_iter(text) {
+            // One group per phrase, so the group says which phrase matched.
+            let matched = (0..PROMPT_INJECTION_PHRASES.len()).find_map(|i| captures.get(i + 1).map(|m| (i, m)));
+            if let Some((i, m)) = matched.filter(|(i, _)| !seen[*i]) {
+                seen[i] = true;
+                let description = format!("Matched phrase '{}'", PROMPT_INJECTION_PHRASES[i].trim());
+                findings.push(Finding::new("Prompt Injection", &description, direction, m.start(), m.end()));
+            }
+        }
+        findings
+    }
+}
+
+/// The phrases as one case-insensitive regex, so spans are offsets into the
+/// text as given rather than into a lowercased copy.
+fn injection_phrases() -> &'static Regex {
+    static PHRASES: OnceLock<Regex> = OnceLock::new();
+    PHRASES.get_or_init(|| {
+        let alternatives: Vec<String> = PROMPT_INJECTION_PHRASES.iter().map(|phrase| format!("({})", regex::escape(phrase))).collect();
+        Regex::new(&format!("(?i){}", alternatives.join("|"))).expect("escaped phrases")
+    })
+}
+
+pub const DETECTOR_NAMES: &[&str] = &["input_patterns", "output_patterns", "prompt_injection"];
+
+/// Which detectors to run. Serialized into `models.model_params` so an
+/// evaluation result can be traced back to the exact configuration.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DetectorConfig {
+    pub detectors: Vec<String>,
+}
+
+impl Default for DetectorConfig {
+    fn default() -> Self {
+        DetectorConfig {
+            detectors: DETECTOR_NAMES.iter().map(|n| n.to_string()).collect(),
+        }
+    }
+}
+
+impl DetectorConfig {
+    /// Builds the configured detectors, loading patterns from the store once.
+    pub async fn build(&self, store: &dyn PatternStore) -> Result<Vec<Box<dyn Detector>>, Box<dyn Error>> {
+        let mut detectors: Vec<Box<dyn Detector>> = Vec::new();
+        for name in &self.detectors {
+            match name.as_str() {
+                "input_patterns" => {
+                    let patterns = store.fetch_patterns(PatternKind::Input).await.map_err(|e| e.to_string())?;
+                    detectors.push(Box::new(PatternDetector::new(name, Direction::Input, patterns)));
+                }
+                "output_patterns" => {
+                    let patterns = store.fetch_patterns(PatternKind::Output).await.map_err(|e| e.to_string())?;
+                    detectors.push(Box::new(PatternDetector::new(name, Direction::Output, patterns)));
+                }
+                "prompt_injection" => detectors.push(Box::new(PromptInjectionDetector)),
+                other => return Err(format!("unknown detector '{}'", other).into()),
+            }
+        }
+        Ok(detectors)
+    }
+}
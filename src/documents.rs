@@ -0,0 +1,601 @@
+use clap::Args;
+use log::warn;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::error::Error;
+use std::fs;
+use std::ops::Range;
+use std::path::Path;
+use std::sync::{Arc, OnceLock};
+use std::time::Instant;
+
+use crate::cli::DetectionArgs;
+use crate::config::Config;
+use crate::db::audit::{AuditWriter, ValidationRecord};
+use crate::db::store::PatternStore;
+use crate::db::Database;
+use crate::findings::{Direction, Finding};
+use crate::guard::{DetectorFailure, Guard, Verdict};
+use crate::logging;
+use crate::metrics;
+use crate::pool::{PoolError, PoolHandle};
+
+/// Rule of the findings for text addressed to the model rather than the
+/// reader.
+pub const ASSISTANT_INSTRUCTIONS_RULE: &str = "Assistant Instructions";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DocumentFormat {
+    /// Plain text, including text extracted from PDFs.
+    Text,
+    Html,
+    Markdown,
+}
+
+impl DocumentFormat {
+    pub fn parse(value: &str) -> Option<DocumentFormat> {
+        match value.to_ascii_lowercase().as_str() {
+            "text" | "txt" | "pdf" => Some(DocumentFormat::Text),
+            "html" | "htm" => Some(DocumentFormat::Html),
+            "markdown" | "md" => Some(DocumentFormat::Markdown),
+            _ => None,
+        }
+    }
+
+    /// From the source's extension, or from the text when that says
+    /// nothing.
+    pub fn guess(source: Option<&str>, text: &str) -> DocumentFormat {
+        let extension = source.and_then(|s| s.rsplit_once('.')).map(|(_, ext)| ext.split(['?', '#']).next().unwrap_or(ext));
+        if let Some(format) = extension.and_then(DocumentFormat::parse) {
+            return format;
+        }
+        if text.trim_start().starts_with('<') {
+            DocumentFormat::Html
+        } else {
+            DocumentFormat::Text
+        }
+    }
+}
+
+/// A retrieved document or chunk of one, headed for a prompt as context.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Document {
+    #[serde(default)]
+    pub id: Option<String>,
+    /// Where it was retrieved from: a URL, path or index name.
+    #[serde(default)]
+    pub source: Option<String>,
+    /// Position of the chunk within the source document.
+    #[serde(default)]
+    pub chunk: Option<u32>,
+    #[serde(default)]
+    pub retrieved_at: Option<String>,
+    /// Guessed from `source` and the text when absent.
+    #[serde(default)]
+    pub format: Option<DocumentFormat>,
+    pub text: String,
+}
+
+impl Document {
+    pub fn new(source: &str, text: &str) -> Document {
+        Document {
+            id: None,
+            source: Some(source.to_string()),
+            chunk: None,
+            retrieved_at: None,
+            format: None,
+            text: text.to_string(),
+        }
+    }
+}
+
+/// Part of a document a reader wouldn't see but the model would.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct HiddenText {
+    /// How it is hidden, e.g. `display:none` or `html comment`.
+    pub kind: String,
+    /// Byte offsets in the document's text.
+    pub start: usize,
+    pub end: usize,
+}
+
+/// Outcome of checking one document, with its provenance so the RAG layer
+/// can drop the chunk and trace it back to where it came from.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct DocumentVerdict {
+    pub id: Option<String>,
+    pub source: Option<String>,
+    pub chunk: Option<u32>,
+    pub retrieved_at: Option<String>,
+    pub format: DocumentFormat,
+    /// Of the text as received, to tie the verdict to exactly what was
+    /// checked.
+    pub sha256: String,
+    pub blocked: bool,
+    /// Spans are byte offsets in the document's text.
+    pub findings: Vec<Finding>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub hidden: Vec<HiddenText>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub failures: Vec<DetectorFailure>,
+}
+
+impl DocumentVerdict {
+    /// The verdict as it would read for a message, to raise alerts with.
+    pub(crate) fn as_verdict(&self) -> Verdict {
+        Verdict {
+            direction: Direction::Input,
+            blocked: self.blocked,
+            findings: self.findings.clone(),
+            failures: self.failures.clone(),
+            session_risk: None,
+        }
+    }
+}
+
+/// A stretch of the scanned text copied from the document.
+#[derive(Debug, Clone, Copy)]
+struct Segment {
+    scanned: usize,
+    original: usize,
+    original_len: usize,
+}
+
+/// Markup that isn't part of the visible text.
+struct Cut {
+    range: Range<usize>,
+    /// What it hides and how, for cuts that hide readable text.
+    hidden: Option<(String, Range<usize>)>,
+}
+
+fn tag_regex() -> &'static Regex {
+    static TAG: OnceLock<Regex> = OnceLock::new();
+    TAG.get_or_init(|| Regex::new(r"(?s)<(/?)([a-zA-Z][a-zA-Z0-9-]*)\b([^>]*?)(/?)>").unwrap())
+}
+
+fn markup_regex() -> &'static Regex {
+    static MARKUP: OnceLock<Regex> = OnceLock::new();
+    MARKUP.get_or_init(|| Regex::new(r"(?s)</?[a-zA-Z][^>]*>").unwrap())
+}
+
+fn hiding_styles() -> &'static [(&'static str, Regex)] {
+    static STYLES: OnceLock<Vec<(&'static str, Regex)>> = OnceLock::new();
+    STYLES.get_or_init(|| {
+        [
+            ("display:none", r"(?i)display\s*:\s*none"),
+            ("visibility:hidden", r"(?i)visibility\s*:\s*hidden"),
+            ("font-size:0", r#"(?i)font-size\s*:\s*(0+(\.0+)?|\.0+)(px|pt|em|rem|%)?\s*(;|$|['"])"#),
+            ("opacity:0", r#"(?i)opacity\s*:\s*(0+(\.0+)?|\.0+)\s*(;|$|['"])"#),
+            // White on the white page most documents are rendered on.
+            ("white text", r"(?i)(^|[^-a-z])color\s*:\s*(white|#fff\b|#ffffff\b|rgb\(\s*255\s*,\s*255\s*,\s*255\s*\))"),
+            ("hidden attribute", r"(?i)(^|\s)hidden(\s|=|/|$)"),
+            ("aria-hidden", r#"(?i)aria-hidden\s*=\s*["']?true"#),
+        ]
+        .into_iter()
+        .map(|(kind, pattern)| (kind, Regex::new(pattern).unwrap()))
+        .collect()
+    })
+}
+
+/// Phrasing aimed at a model reading the document rather than at a person.
+fn assistant_instructions() -> &'static [Regex] {
+    static PATTERNS: OnceLock<Vec<Regex>> = OnceLock::new();
+    PATTERNS.get_or_init(|| {
+        [
+            r"\bif you are (an? )?(ai|assistant|chatbot|llm|(large )?language model)\b",
+            r"\b(note|message|instructions?|attention) (to|for) (the |any )?(ai|assistant|chatbot|llm|language model|model)s?\b",
+            r"\b(ai|assistant|chatbot|llm)s? (reading|processing|summari[sz]ing) this\b",
+            r"\b(ignore|disregard|forget|override) (all |any )?(of )?(the |your )?(previous|prior|above|earlier|preceding|original) (instructions?|prompts?|directions|rules|context)\b",
+            r"\b(new|updated|real|actual|hidden) (system )?instructions?\s*:",
+            r"\bwhen you (summari[sz]e|answer|respond to|read) (this|these)\b",
+            r"\b(do not|don't|never) (tell|reveal|mention|inform) (this to )?the user\b",
+            r"\b(tell|inform|convince) the user (to|that)\b",
+            r"\byou are now (a|an|in|the)\b",
+            r"(?m)^\s*(system|assistant)\s*:",
+        ]
+        .iter()
+        .map(|pattern| Regex::new(&format!("(?i){}", pattern)).unwrap())
+        .collect()
+    })
+}
+
+/// Elements that separate words when stripped; inline ones don't, so
+/// `ig<b>nore</b>` still reads `ignore`.
+fn separates_words(tag: &str) -> bool {
+    const INLINE: &[&str] = &["a", "abbr", "b", "bdi", "bdo", "code", "em", "font", "i", "kbd", "mark", "q", "s", "small", "span", "strong", "sub", "sup", "u"];
+    let name = tag.trim_start_matches(['<', '/']);
+    let name: String = name.chars().take_while(|c| c.is_ascii_alphanumeric()).collect();
+    !INLINE.contains(&name.to_ascii_lowercase().as_str())
+}
+
+const VOID_ELEMENTS: &[&str] = &["area", "base", "br", "col", "embed", "hr", "img", "input", "link", "meta", "source", "track", "wbr"];
+
+fn decode_entities(text: &str) -> String {
+    static ENTITY: OnceLock<Regex> = OnceLock::new();
+    let entity = ENTITY.get_or_init(|| Regex::new(r"&(#[xX][0-9a-fA-F]+|#[0-9]+|[a-zA-Z]+);").unwrap());
+    entity
+        .replace_all(text, |caps: &regex::Captures| {
+            let name = &caps[1];
+            let decoded = match name {
+                "amp" => Some('&'),
+                "lt" => Some('<'),
+                "gt" => Some('>'),
+                "quot" => Some('"'),
+                "apos" => Some('\''),
+                "nbsp" => Some(' '),
+                _ if name.starts_with("#x") || name.starts_with("#X") => u32::from_str_radix(&name[2..], 16).ok().and_then(char::from_u32),
+                _ if name.starts_with('#') => name[1..].parse().ok().and_then(char::from_u32),
+                _ => None,
+            };
+            decoded.map(String::from).unwrap_or_else(|| caps[0].to_string())
+        })
+        .into_owned()
+}
+
+/// Comments, scripts and hidden elements of an HTML or markdown document,
+/// in document order and not overlapping.
+fn find_cuts(text: &str, format: DocumentFormat) -> Vec<Cut> {
+    static COMMENT: OnceLock<Regex> = OnceLock::new();
+    static CODE: OnceLock<Regex> = OnceLock::new();
+    static LINK_COMMENT: OnceLock<Regex> = OnceLock::new();
+    let comment = COMMENT.get_or_init(|| Regex::new(r"(?s)<!--(.*?)(-->|$)").unwrap());
+    let code = CODE.get_or_init(|| Regex::new(r"(?is)<script\b[^>]*>.*?(</script\s*>|$)|<style\b[^>]*>.*?(</style\s*>|$)").unwrap());
+    // `[//]: # (text)`, markdown's usual stand-in for a comment.
+    let link_comment = LINK_COMMENT.get_or_init(|| Regex::new(r#"(?m)^[ \t]*\[[^\]]*\]:[ \t]*#[ \t]+[("'](.*)[)"'][ \t]*$"#).unwrap());
+
+    let mut cuts: Vec<Cut> = Vec::new();
+    for caps in comment.captures_iter(text) {
+        let inner = caps.get(1).unwrap();
+        cuts.push(Cut {
+            range: caps.get(0).unwrap().range(),
+            hidden: Some(("html comment".to_string(), inner.range())),
+        });
+    }
+    if format == DocumentFormat::Markdown {
+        for caps in link_comment.captures_iter(text) {
+            cuts.push(Cut {
+                range: caps.get(0).unwrap().range(),
+                hidden: Some(("markdown comment".to_string(), caps.get(1).unwrap().range())),
+            });
+        }
+    }
+    for m in code.find_iter(text) {
+        cuts.push(Cut { range: m.range(), hidden: None });
+    }
+
+    // One pass pairs every element with its closing tag: each close ends
+    // the innermost open element of the same name.
+    let tags: Vec<regex::Captures> = tag_regex().captures_iter(text).collect();
+    let mut closes: Vec<Option<Range<usize>>> = vec![None; tags.len()];
+    let mut unclosed: HashMap<String, Vec<usize>> = HashMap::new();
+    for (i, tag) in tags.iter().enumerate() {
+        if !tag[4].is_empty() {
+            continue;
+        }
+        let name = tag[2].to_ascii_lowercase();
+        if tag[1].is_empty() {
+            unclosed.entry(name).or_default().push(i);
+        } else if let Some(opened) = unclosed.get_mut(&name).and_then(Vec::pop) {
+            closes[opened] = Some(tag.get(0).unwrap().range());
+        }
+    }
+
+    for (i, open) in tags.iter().enumerate() {
+        let name = open[2].to_ascii_lowercase();
+        if !open[1].is_empty() || !open[4].is_empty() || VOID_ELEMENTS.contains(&name.as_str()) {
+            continue;
+        }
+        let kind = match hiding_styles().iter().find(|(_, style)| style.is_match(&open[3])) {
+            Some((kind, _)) => *kind,
+            None => continue,
+        };
+        let open_range = open.get(0).unwrap().range();
+        // An element that is never closed hides the rest of the document.
+        let close = closes[i].clone().unwrap_or(text.len()..text.len());
+        cuts.push(Cut {
+            range: open_range.start..close.end,
+            hidden: Some((kind.to_string(), open_range.end..close.start)),
+        });
+    }
+
+    cuts.sort_by_key(|cut| cut.range.start);
+    let mut kept: Vec<Cut> = Vec::new();
+    for cut in cuts {
+        match kept.last() {
+            Some(last) if cut.range.start < last.range.end => {}
+            _ => kept.push(cut),
+        }
+    }
+    kept
+}
+
+/// A document as the detectors see it: the visible text, then each piece of
+/// hidden text, with the markup stripped and entities decoded.
+#[derive(Debug, Clone)]
+pub struct ScannedDocument {
+    document: Document,
+    format: DocumentFormat,
+    /// What the detectors run over.
+    pub text: String,
+    segments: Vec<Segment>,
+    hidden: Vec<(HiddenText, Range<usize>)>,
+}
+
+impl ScannedDocument {
+    pub fn new(document: Document) -> ScannedDocument {
+        ScannedDocument::up_to(document, usize::MAX)
+    }
+
+    /// Scans only the first `limit` bytes of the document, as cut by
+    /// `max_input_bytes`.
+    pub fn up_to(document: Document, limit: usize) -> ScannedDocument {
+        let format = document.format.unwrap_or_else(|| DocumentFormat::guess(document.source.as_deref(), &document.text));
+        let mut scanned = ScannedDocument {
+            document,
+            format,
+            text: String::new(),
+            segments: Vec::new(),
+            hidden: Vec::new(),
+        };
+        let mut end = limit.min(scanned.document.text.len());
+        while !scanned.document.text.is_char_boundary(end) {
+            end -= 1;
+        }
+        let original = scanned.document.text[..end].to_string();
+        if format == DocumentFormat::Text {
+            scanned.push(&original, 0..original.len());
+            return scanned;
+        }
+
+        let cuts = find_cuts(&original, format);
+        let mut position = 0;
+        for cut in &cuts {
+            scanned.push_markup(&original, position..cut.range.start);
+            scanned.separate();
+            position = cut.range.end;
+        }
+        scanned.push_markup(&original, position..original.len());
+
+        for cut in cuts {
+            if let Some((kind, range)) = cut.hidden {
+                if original[range.clone()].trim().is_empty() {
+                    continue;
+                }
+                scanned.text.push('\n');
+                let start = scanned.text.len();
+                scanned.push_markup(&original, range.clone());
+                let hidden = HiddenText {
+                    kind,
+                    start: range.start,
+                    end: range.end,
+                };
+                scanned.hidden.push((hidden, start..scanned.text.len()));
+            }
+        }
+        scanned
+    }
+
+    fn push(&mut self, original: &str, range: Range<usize>) {
+        if range.is_empty() {
+            return;
+        }
+        self.segments.push(Segment {
+            scanned: self.text.len(),
+            original: range.start,
+            original_len: range.len(),
+        });
+        self.text.push_str(&decode_entities(&original[range]));
+    }
+
+    fn separate(&mut self) {
+        if !self.text.is_empty() && !self.text.ends_with(char::is_whitespace) {
+            self.text.push(' ');
+        }
+    }
+
+    /// Copies `range` of the document without its tags.
+    fn push_markup(&mut self, original: &str, range: Range<usize>) {
+        let mut position = range.start;
+        for tag in markup_regex().find_iter(&original[range.clone()]) {
+            self.push(original, position..range.start + tag.start());
+            if separates_words(tag.as_str()) {
+                self.separate();
+            }
+            position = range.start + tag.end();
+        }
+        self.push(original, position..range.end);
+    }
+
+    /// Maps an offset in `text` back to the document. Offsets inside a
+    /// stretch with decoded entities are approximate.
+    fn to_original(&self, position: usize) -> usize {
+        let index = self.segments.partition_point(|s| s.scanned <= position);
+        match index.checked_sub(1).map(|i| self.segments[i]) {
+            Some(segment) => segment.original + (position - segment.scanned).min(segment.original_len),
+            None => 0,
+        }
+    }
+
+    /// Findings for text addressed to the model.
+    pub fn assistant_instructions(&self) -> Vec<Finding> {
+        assistant_instructions()
+            .iter()
+            .filter_map(|pattern| pattern.find(&self.text))
+            .map(|m| {
+                let description = format!("Instruction aimed at the assistant: '{}'", m.as_str().trim());
+                Finding::new(ASSISTANT_INSTRUCTIONS_RULE, &description, Direction::Input, m.start(), m.end())
+            })
+            .collect()
+    }
+
+    /// Combines the detectors' verdict on `text` with the document's own
+    /// checks. Findings in hidden text say how it was hidden.
+    pub fn verdict(self, verdict: Verdict) -> DocumentVerdict {
+        let mut findings = Vec::new();
+        for mut finding in verdict.findings.into_iter().chain(self.assistant_instructions()) {
+            // Failed detectors have no span to map.
+            if finding.end > finding.start {
+                if let Some((hidden, _)) = self.hidden.iter().find(|(_, scanned)| scanned.contains(&finding.start)) {
+                    finding.description = format!("In text hidden by {}: {}", hidden.kind, finding.description);
+                }
+                finding.start = self.to_original(finding.start);
+                finding.end = self.to_original(finding.end);
+            }
+            findings.push(finding);
+        }
+        let sha256 = Sha256::digest(self.document.text.as_bytes()).iter().map(|b| format!("{:02x}", b)).collect();
+        DocumentVerdict {
+            id: self.document.id,
+            source: self.document.source,
+            chunk: self.document.chunk,
+            retrieved_at: self.document.retrieved_at,
+            format: self.format,
+            sha256,
+            blocked: verdict.blocked || !findings.is_empty(),
+            findings,
+            hidden: self.hidden.into_iter().map(|(hidden, _)| hidden).collect(),
+            failures: verdict.failures,
+        }
+    }
+}
+
+/// Checks a retrieved document before it goes into a prompt. The visible
+/// text and any hidden text are run through the guard's detectors as input,
+/// and looked over for instructions aimed at the assistant. The app's
+/// `max_input_bytes` applies to the document as received, and the scanning
+/// runs on the guard's compute pool.
+pub async fn check_document(guard: &Guard, document: Document, app: Option<&str>) -> DocumentVerdict {
+    let started = Instant::now();
+    let (scan, verdict) = guard.cap_document(&document.text, app);
+    let limit = match scan {
+        Some(limit) => limit,
+        None => return ScannedDocument::up_to(document, 0).verdict(verdict),
+    };
+    let scanned = guard.compute().run(move || ScannedDocument::up_to(document, limit)).await;
+    let verdict = guard.check_within_cap(&scanned.text, app, started, verdict).await;
+    scanned.verdict(verdict)
+}
+
+/// Reply to a `serve` or `stream` message carrying `documents`.
+#[derive(Debug, Serialize)]
+pub struct DocumentBatch {
+    /// Whether any document was blocked; drop those, keep the rest.
+    pub blocked: bool,
+    pub documents: Vec<DocumentVerdict>,
+}
+
+impl DocumentBatch {
+    pub fn new(documents: Vec<DocumentVerdict>) -> DocumentBatch {
+        DocumentBatch {
+            blocked: documents.iter().any(|d| d.blocked),
+            documents,
+        }
+    }
+
+    /// Logs each blocked document with where it came from.
+    pub fn log_blocked(&self, request_id: &str) {
+        for document in self.documents.iter().filter(|d| d.blocked) {
+            warn!(
+                request_id = request_id,
+                source = document.source.as_deref().unwrap_or(""),
+                document_id = document.id.as_deref().unwrap_or(""),
+                sha256 = document.sha256.as_str(),
+                findings = document.findings.len();
+                "Blocked document"
+            );
+        }
+    }
+}
+
+/// Like [`check_document`], for each document in turn on the worker pool.
+pub async fn check_on_pool(pool: &PoolHandle, documents: Vec<Document>, app: Option<String>) -> Result<Vec<DocumentVerdict>, PoolError> {
+    let mut verdicts = Vec::with_capacity(documents.len());
+    for document in documents {
+        let started = Instant::now();
+        let verdict = pool.submit_document(document, app.clone()).await?;
+        metrics::record_verdict("documents", Direction::Input, verdict.blocked, started.elapsed());
+        verdicts.push(verdict);
+    }
+    Ok(verdicts)
+}
+
+#[derive(Debug, Args)]
+pub struct DocumentsArgs {
+    /// `.jsonl` files of documents (`{"source": ..., "text": ...}`), or
+    /// HTML, markdown and text files, or directories of them.
+    #[arg(required = true)]
+    pub paths: Vec<String>,
+
+    #[command(flatten)]
+    pub detection: DetectionArgs,
+}
+
+fn read_documents(path: &Path, documents: &mut Vec<Document>) -> Result<(), Box<dyn Error>> {
+    if path.is_dir() {
+        let mut entries: Vec<_> = fs::read_dir(path)?.collect::<Result<Vec<_>, _>>()?.into_iter().map(|e| e.path()).collect();
+        entries.sort();
+        for entry in entries.iter().filter(|e| e.is_file()) {
+            read_documents(entry, documents)?;
+        }
+        return Ok(());
+    }
+
+    let content = fs::read_to_string(path)?;
+    if path.extension().map_or(false, |ext| ext == "jsonl") {
+        for (i, line) in content.lines().enumerate().filter(|(_, line)| !line.trim().is_empty()) {
+            let document = serde_json::from_str(line).map_err(|e| format!("{}:{}: {}", path.display(), i + 1, e))?;
+            documents.push(document);
+        }
+    } else {
+        documents.push(Document::new(&path.display().to_string(), &content));
+    }
+    Ok(())
+}
+
+/// Handles `documents <path>... [--detectors a,b] [--policy FILE]`.
+///
+/// Prints one JSON verdict per document and returns whether any was blocked
+/// so the caller can exit with `EXIT_BLOCKED`. With `sinks.audit` enabled
+/// each document is recorded in the audit trail under its id or source,
+/// which needs `db`.
+pub async fn run_documents_command(args: &DocumentsArgs, config: &Config, db: Option<&Database>, store: Arc<dyn PatternStore>) -> Result<bool, Box<dyn Error>> {
+    let guard = config.detection.guard(store, config.workers.compute_pool()?).await?;
+    let audit = AuditWriter::from_config(&config.sinks.audit, db);
+    let mut documents = Vec::new();
+    for path in &args.paths {
+        read_documents(Path::new(path), &mut documents)?;
+    }
+
+    let mut blocked = 0;
+    let total = documents.len();
+    for document in documents {
+        let started = Instant::now();
+        let content = logging::content(&document.text);
+        let verdict = check_document(&guard, document, None).await;
+        metrics::record_verdict("documents", Direction::Input, verdict.blocked, started.elapsed());
+        if let Some((writer, _)) = &audit {
+            let request_id = verdict.id.as_deref().or(verdict.source.as_deref()).unwrap_or_default();
+            writer.record(ValidationRecord::new(Direction::Input, verdict.findings.clone(), started.elapsed()).for_request(request_id, None));
+        }
+        if verdict.blocked {
+            blocked += 1;
+            warn!(
+                source = verdict.source.as_deref().unwrap_or(""),
+                sha256 = verdict.sha256.as_str(),
+                findings = verdict.findings.len(),
+                content = content.as_str();
+                "Blocked document"
+            );
+        }
+        println!("{}", serde_json::to_string(&verdict)?);
+    }
+    eprintln!("Checked {} documents: {} blocked", total, blocked);
+    if let Some((writer, task)) = audit {
+        writer.finish(task).await;
+    }
+
+    Ok(blocked > 0)
+}
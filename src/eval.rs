@@ -0,0 +1,138 @@
+use clap::Args;
+use serde::{Deserialize, Serialize};
+use std::error::Error;
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+
+use crate::cli::DetectionArgs;
+use crate::config::Config;
+use crate::datasets::{parse_split, split_for, Split};
+use crate::db::experiments::ConfusionMatrix;
+use crate::db::store::PatternStore;
+use crate::db::Database;
+use crate::detectors::Detector;
+use crate::findings::Direction;
+use crate::policy::Policy;
+
+/// Expected verdict for a labeled example.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Label {
+    #[serde(alias = "attack")]
+    Malicious,
+    #[serde(alias = "safe")]
+    Benign,
+}
+
+/// One line of a labeled dataset.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LabeledExample {
+    pub text: String,
+    pub direction: Direction,
+    pub label: Label,
+    #[serde(default)]
+    pub category: Option<String>,
+}
+
+impl LabeledExample {
+    pub fn is_malicious(&self) -> bool {
+        self.label == Label::Malicious
+    }
+}
+
+/// Reads a JSONL file with one `LabeledExample` per line. Blank lines are
+/// skipped; a malformed line fails with its line number.
+pub fn load_examples(path: &str) -> Result<Vec<LabeledExample>, Box<dyn Error>> {
+    let reader = BufReader::new(File::open(path)?);
+    let mut examples = Vec::new();
+    for (number, line) in reader.lines().enumerate() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let example = serde_json::from_str(&line).map_err(|e| format!("{}:{}: {}", path, number + 1, e))?;
+        examples.push(example);
+    }
+    Ok(examples)
+}
+
+/// Runs every example through the detectors. An example counts as flagged
+/// when any detector's score crosses its policy threshold.
+pub fn evaluate(examples: &[LabeledExample], detectors: &[Box<dyn Detector>], policy: &Policy) -> ConfusionMatrix {
+    let mut matrix = ConfusionMatrix::default();
+    for example in examples {
+        let flagged = !policy.findings(detectors, &example.text, example.direction).is_empty();
+        matrix.record(flagged, example.is_malicious());
+    }
+    matrix
+}
+
+#[derive(Debug, Args)]
+pub struct EvalArgs {
+    pub dataset_id: i32,
+
+    /// Name stored in `models`; defaults to the detector list.
+    #[arg(long)]
+    pub model_name: Option<String>,
+
+    /// train, validation or test. `tune` only sees the first two, so the
+    /// default keeps its thresholds from being scored on what they were
+    /// fitted to.
+    #[arg(long, default_value = "test", value_parser = parse_split)]
+    pub split: Split,
+
+    #[command(flatten)]
+    pub detection: DetectionArgs,
+}
+
+/// Handles `eval <dataset_id> [--split S] [--detectors a,b] [--policy FILE] [--model-name NAME]`.
+///
+/// The detector configuration and the thresholds it ran with are stored as a
+/// `models` row in the dataset's session and the outcome as a `results` row
+/// pointing at both, so quality can be compared across releases.
+pub async fn run_eval_command(args: &EvalArgs, config: &Config, db: &Database, store: &dyn PatternStore) -> Result<(), Box<dyn Error>> {
+    let dataset_id = args.dataset_id;
+    let detector_config = config.detection.detector_config();
+    let model_name = args.model_name.clone().unwrap_or_else(|| format!("detectors:{}", detector_config.detectors.join("+")));
+
+    let dataset = db.fetch_dataset(dataset_id).await?;
+    let examples: Vec<LabeledExample> = crate::datasets::load_dataset(&dataset)?
+        .into_iter()
+        .filter(|e| split_for(&e.text) == args.split)
+        .collect();
+    let (detectors, policy) = config.detection.load(store).await?;
+    let matrix = evaluate(&examples, &detectors, &policy);
+    let metrics = matrix.metrics();
+
+    let thresholds: serde_json::Map<String, serde_json::Value> = detectors
+        .iter()
+        .map(|d| (d.name().to_string(), serde_json::json!(policy.threshold_for(d.name()))))
+        .collect();
+    let params = serde_json::json!({
+        "detectors": detector_config.detectors,
+        "thresholds": thresholds,
+        "policy": config.detection.policy,
+        "split": args.split.as_str(),
+        "version": env!("CARGO_PKG_VERSION"),
+    });
+    let model_id = db.add_model(&dataset.session_id, &model_name, "detector_config", &params).await?;
+    let result_id = db.add_result(&dataset.session_id, model_id, Some(dataset_id), &matrix).await?;
+
+    println!(
+        "Evaluated {} {} examples from '{}' with {}",
+        matrix.total(),
+        args.split.as_str(),
+        dataset.dataset_name,
+        model_name
+    );
+    println!("                 actual malicious  actual benign");
+    println!("flagged          {:>16}  {:>13}", matrix.true_positives, matrix.false_positives);
+    println!("not flagged      {:>16}  {:>13}", matrix.false_negatives, matrix.true_negatives);
+    println!(
+        "accuracy {:.4}  precision {:.4}  recall {:.4}  f1 {:.4}",
+        metrics.accuracy, metrics.precision, metrics.recall, metrics.f1_score
+    );
+    println!("Stored as model {} / result {}", model_id, result_id);
+
+    Ok(())
+}
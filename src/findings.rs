@@ -0,0 +1,49 @@
+use serde::{Deserialize, Serialize};
+
+/// Whether a message was on its way into the model or coming back out of it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Direction {
+    Input,
+    Output,
+}
+
+impl Direction {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Direction::Input => "input",
+            Direction::Output => "output",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Direction> {
+        match value.to_ascii_lowercase().as_str() {
+            "input" => Some(Direction::Input),
+            "output" => Some(Direction::Output),
+            _ => None,
+        }
+    }
+}
+
+/// A single rule that matched a message.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Finding {
+    pub rule: String,
+    pub description: String,
+    pub direction: Direction,
+    /// Byte offsets of the match in the validated text.
+    pub start: usize,
+    pub end: usize,
+}
+
+impl Finding {
+    pub fn new(rule: &str, description: &str, direction: Direction, start: usize, end: usize) -> Self {
+        Finding {
+            rule: rule.to_string(),
+            description: description.to_string(),
+            direction,
+            start,
+            end,
+        }
+    }
+}
@@ -0,0 +1,428 @@
+use log::warn;
+use serde::Serialize;
+use std::any::Any;
+use std::error::Error;
+use std::fmt;
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::Arc;
+use std::time::Instant;
+use tracing::{info_span, Instrument, Span};
+
+use crate::compute::ComputePool;
+use crate::db::store::{MemoryPatternStore, PatternStore, StoreError};
+use crate::detectors::{Detector, DetectorConfig, DETECTOR_NAMES};
+use crate::documents::{self, Document, DocumentVerdict};
+use crate::findings::{Direction, Finding};
+use crate::metrics;
+use crate::policy::{FailMode, Policy};
+
+/// Why a detector gave no answer for a message.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FailureKind {
+    Timeout,
+    Error,
+    InputTooLarge,
+}
+
+impl FailureKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            FailureKind::Timeout => "timeout",
+            FailureKind::Error => "error",
+            FailureKind::InputTooLarge => "input_too_large",
+        }
+    }
+
+    /// Rule name of the finding recorded when the failure blocks a message.
+    pub fn rule(&self) -> &'static str {
+        match self {
+            FailureKind::Timeout => "Detector Timeout",
+            FailureKind::Error => "Detector Error",
+            FailureKind::InputTooLarge => "Input Too Large",
+        }
+    }
+}
+
+/// A detector that timed out or failed on a message, and what the policy did
+/// about it.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[non_exhaustive]
+pub struct DetectorFailure {
+    pub detector: String,
+    pub kind: FailureKind,
+    pub message: String,
+    pub on_error: FailMode,
+}
+
+/// Outcome of checking one message.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[non_exhaustive]
+pub struct Verdict {
+    pub direction: Direction,
+    /// Whether any detector crossed its policy threshold, or failed closed.
+    pub blocked: bool,
+    /// Findings from the detectors that crossed their threshold, plus one per
+    /// detector that failed closed.
+    pub findings: Vec<Finding>,
+    /// Detectors that timed out or failed, whichever way they failed.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub failures: Vec<DetectorFailure>,
+    /// The conversation's risk after this turn, for messages checked as
+    /// part of a session.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub session_risk: Option<f32>,
+}
+
+impl Verdict {
+    fn new(direction: Direction) -> Self {
+        Verdict {
+            direction,
+            blocked: false,
+            findings: Vec::new(),
+            failures: Vec::new(),
+            session_risk: None,
+        }
+    }
+
+    pub fn is_allowed(&self) -> bool {
+        !self.blocked
+    }
+
+    fn fail(&mut self, detector: &str, kind: FailureKind, message: String, on_error: FailMode) {
+        metrics::metrics().detector_failures.with_label_values(&[detector, kind.as_str()]).inc();
+        if on_error == FailMode::Closed {
+            let description = format!("{}: {}", detector, message);
+            self.findings.push(Finding::new(kind.rule(), &description, self.direction, 0, 0));
+        }
+        self.failures.push(DetectorFailure {
+            detector: detector.to_string(),
+            kind,
+            message,
+            on_error,
+        });
+    }
+
+    fn finish(mut self) -> Self {
+        self.blocked = !self.findings.is_empty();
+        self
+    }
+}
+
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum GuardError {
+    /// A detector name the library doesn't provide.
+    UnknownDetector(String),
+    /// The builder ended up with nothing to run.
+    NoDetectors,
+    /// Loading patterns from the store failed.
+    Store(StoreError),
+    /// `build_sync` couldn't start its runtime.
+    Runtime(std::io::Error),
+}
+
+impl fmt::Display for GuardError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            GuardError::UnknownDetector(name) => write!(f, "unknown detector '{}', expected one of {}", name, DETECTOR_NAMES.join(", ")),
+            GuardError::NoDetectors => write!(f, "at least one detector is required"),
+            GuardError::Store(e) => write!(f, "failed to load patterns: {}", e),
+            GuardError::Runtime(e) => write!(f, "failed to start a runtime: {}", e),
+        }
+    }
+}
+
+impl Error for GuardError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            GuardError::Store(e) => Some(e.as_ref()),
+            GuardError::Runtime(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+/// Configures a [`Guard`]. Everything is optional: by default every built-in
+/// detector runs with the built-in patterns and default thresholds.
+#[derive(Default)]
+pub struct GuardBuilder {
+    policy: Option<Policy>,
+    store: Option<Arc<dyn PatternStore>>,
+    detectors: Option<Vec<String>>,
+    custom: Vec<Box<dyn Detector>>,
+    compute: Option<ComputePool>,
+}
+
+impl GuardBuilder {
+    pub fn with_policy(mut self, policy: Policy) -> Self {
+        self.policy = Some(policy);
+        self
+    }
+
+    /// Where the pattern detectors load their patterns from.
+    pub fn with_store<S: PatternStore + 'static>(self, store: S) -> Self {
+        self.with_shared_store(Arc::new(store))
+    }
+
+    pub fn with_shared_store(mut self, store: Arc<dyn PatternStore>) -> Self {
+        self.store = Some(store);
+        self
+    }
+
+    /// Which built-in detectors to run, by name.
+    pub fn with_detectors<I, S>(mut self, names: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.detectors = Some(names.into_iter().map(Into::into).collect());
+        self
+    }
+
+    /// Adds a detector of your own next to the built-in ones.
+    pub fn with_detector<D: Detector + 'static>(mut self, detector: D) -> Self {
+        self.custom.push(Box::new(detector));
+        self
+    }
+
+    /// Threads the async checks run detectors on. Defaults to a pool shared
+    /// by every guard in the process.
+    pub fn with_compute_pool(mut self, compute: ComputePool) -> Self {
+        self.compute = Some(compute);
+        self
+    }
+
+    /// Loads the patterns once and returns a guard ready to check messages.
+    ///
+    /// A detector whose patterns can't be loaded is left out with a warning
+    /// if the policy lets it fail open; otherwise building fails.
+    pub async fn build(self) -> Result<Guard, GuardError> {
+        let names = self.detectors.unwrap_or_else(|| DETECTOR_NAMES.iter().map(|n| n.to_string()).collect());
+        if let Some(unknown) = names.iter().find(|n| !DETECTOR_NAMES.contains(&n.as_str())) {
+            return Err(GuardError::UnknownDetector(unknown.clone()));
+        }
+
+        let policy = self.policy.unwrap_or_default();
+        let store = self.store.unwrap_or_else(|| Arc::new(MemoryPatternStore::with_defaults()));
+        let mut detectors: Vec<Arc<dyn Detector>> = Vec::new();
+        for name in names {
+            let built = (DetectorConfig { detectors: vec![name.clone()] })
+                .build(store.as_ref())
+                .instrument(info_span!("fetch_patterns", detector = name.as_str()))
+                .await;
+            if name.ends_with("_patterns") {
+                let result = if built.is_ok() { "ok" } else { "failed" };
+                metrics::metrics().pattern_loads.with_label_values(&[&name, result]).inc();
+            }
+            match built {
+                Ok(built) => detectors.extend(built.into_iter().map(Arc::from)),
+                Err(e) if policy.fail_mode(&name, None) == FailMode::Open => {
+                    warn!("Skipping detector {}, its patterns failed to load: {}", name, e);
+                }
+                Err(e) => return Err(GuardError::Store(e.to_string().into())),
+            }
+        }
+        detectors.extend(self.custom.into_iter().map(Arc::from));
+        if detectors.is_empty() {
+            return Err(GuardError::NoDetectors);
+        }
+
+        Ok(Guard {
+            detectors,
+            policy: Arc::new(policy),
+            compute: self.compute.unwrap_or_else(ComputePool::shared),
+        })
+    }
+
+    /// Blocking version of [`build`](Self::build) for code without an async
+    /// runtime. Must not be called from inside one.
+    pub fn build_sync(self) -> Result<Guard, GuardError> {
+        tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .map_err(GuardError::Runtime)?
+            .block_on(self.build())
+    }
+}
+
+fn panic_message(payload: Box<dyn Any + Send>) -> String {
+    match payload.downcast::<String>() {
+        Ok(message) => *message,
+        Err(payload) => match payload.downcast::<&str>() {
+            Ok(message) => message.to_string(),
+            Err(_) => "detector panicked".to_string(),
+        },
+    }
+}
+
+/// Checks prompts and responses against a fixed set of detectors and a
+/// policy. Patterns are loaded when the guard is built, so checks never touch
+/// the store. Share one guard between tasks with an `Arc`.
+///
+/// The async checks enforce every limit in the policy. The `_sync` checks
+/// enforce the input cap and catch detector panics, but can't interrupt a
+/// slow detector, so they ignore timeouts and the request budget.
+pub struct Guard {
+    detectors: Vec<Arc<dyn Detector>>,
+    policy: Arc<Policy>,
+    compute: ComputePool,
+}
+
+impl Guard {
+    pub fn builder() -> GuardBuilder {
+        GuardBuilder::default()
+    }
+
+    pub fn policy(&self) -> &Policy {
+        &self.policy
+    }
+
+    /// Checks a prompt before it goes to the model.
+    pub async fn check_input(&self, text: &str) -> Verdict {
+        self.check_message(text, Direction::Input, None).await
+    }
+
+    /// Checks a response before it goes back to the user.
+    pub async fn check_output(&self, text: &str) -> Verdict {
+        self.check_message(text, Direction::Output, None).await
+    }
+
+    /// Checks a retrieved document, such as a web page, a markdown file or the
+    /// text of a PDF, before it goes into a prompt as context. Hidden text
+    /// and instructions aimed at the assistant are reported along with the
+    /// detectors' findings.
+    pub async fn check_document(&self, document: Document) -> DocumentVerdict {
+        documents::check_document(self, document, None).await
+    }
+
+    pub fn check_input_sync(&self, text: &str) -> Verdict {
+        self.check(text, Direction::Input)
+    }
+
+    pub fn check_output_sync(&self, text: &str) -> Verdict {
+        self.check(text, Direction::Output)
+    }
+
+    /// Applies `max_input_bytes`. Returns the text to validate, or `None`
+    /// when the message is blocked outright.
+    fn cap_input<'a>(&self, text: &'a str, app: Option<&str>, verdict: &mut Verdict) -> Option<&'a str> {
+        let limit = match self.policy.max_input_bytes(app) {
+            Some(limit) if text.len() > limit => limit,
+            _ => return Some(text),
+        };
+        let on_error = self.policy.input_fail_mode(app);
+        let message = format!("{} bytes is over the {} byte limit", text.len(), limit);
+        verdict.fail("max_input_bytes", FailureKind::InputTooLarge, message, on_error);
+        match on_error {
+            FailMode::Closed => None,
+            FailMode::Open => {
+                let mut end = limit;
+                while !text.is_char_boundary(end) {
+                    end -= 1;
+                }
+                Some(&text[..end])
+            }
+        }
+    }
+
+    /// Runs the detectors one after another on the calling thread.
+    pub fn check(&self, text: &str, direction: Direction) -> Verdict {
+        let mut verdict = Verdict::new(direction);
+        let text = match self.cap_input(text, None, &mut verdict) {
+            Some(text) => text,
+            None => return verdict.finish(),
+        };
+
+        for detector in &self.detectors {
+            match panic::catch_unwind(AssertUnwindSafe(|| self.policy.detector_findings(detector.as_ref(), text, direction))) {
+                Ok(findings) => verdict.findings.extend(findings),
+                Err(payload) => {
+                    let on_error = self.policy.fail_mode(detector.name(), None);
+                    verdict.fail(detector.name(), FailureKind::Error, panic_message(payload), on_error);
+                }
+            }
+        }
+        verdict.finish()
+    }
+
+    /// Checks a message from `app` with every limit in the policy applied,
+    /// using the app's overrides when it has any.
+    ///
+    /// The detectors run side by side on the compute pool. Each one gets its
+    /// own timeout, cut short by whatever is left of the request budget; one
+    /// that overruns is recorded as failed and the verdict doesn't wait for
+    /// it, although it keeps its compute thread until it finishes.
+    pub async fn check_message(&self, text: &str, direction: Direction, app: Option<&str>) -> Verdict {
+        let started = Instant::now();
+        let mut verdict = Verdict::new(direction);
+        let capped = info_span!("normalize", bytes = text.len()).in_scope(|| self.cap_input(text, app, &mut verdict));
+        match capped {
+            Some(text) => self.check_within_cap(text, app, started, verdict).await,
+            None => verdict.finish(),
+        }
+    }
+
+    /// Applies `max_input_bytes` to a document's text before it is scanned.
+    /// Returns how many bytes of it to scan, or `None` with the finished
+    /// verdict when it is blocked outright.
+    pub(crate) fn cap_document(&self, text: &str, app: Option<&str>) -> (Option<usize>, Verdict) {
+        let mut verdict = Verdict::new(Direction::Input);
+        match self.cap_input(text, app, &mut verdict) {
+            Some(text) => (Some(text.len()), verdict),
+            None => (None, verdict.finish()),
+        }
+    }
+
+    pub(crate) fn compute(&self) -> &ComputePool {
+        &self.compute
+    }
+
+    /// Runs the detectors over text already cut to `max_input_bytes`, adding
+    /// to `verdict`. The request budget counts from `started`.
+    pub(crate) async fn check_within_cap(&self, text: &str, app: Option<&str>, started: Instant, mut verdict: Verdict) -> Verdict {
+        let direction = verdict.direction;
+        let text: Arc<str> = Arc::from(text);
+        let budget_deadline = self.policy.request_budget(app).map(|budget| started + budget);
+
+        let running: Vec<_> = self
+            .detectors
+            .iter()
+            .filter(|d| d.applies_to(direction))
+            .map(|detector| {
+                let (task_detector, policy, text) = (detector.clone(), self.policy.clone(), text.clone());
+                // Compute threads don't inherit the caller's span.
+                let parent = Span::current();
+                let task = self
+                    .compute
+                    .submit(move || parent.in_scope(|| policy.detector_findings(task_detector.as_ref(), &text, direction)));
+                (detector, task)
+            })
+            .collect();
+
+        for (detector, task) in running {
+            let name = detector.name();
+            let timeout = self.policy.detector_timeout(name, app);
+            let deadline = match (timeout.map(|t| started + t), budget_deadline) {
+                (Some(a), Some(b)) => Some(a.min(b)),
+                (a, b) => a.or(b),
+            };
+            let outcome = match deadline {
+                Some(deadline) => tokio::time::timeout_at(deadline.into(), task).await.ok(),
+                None => Some(task.await),
+            };
+
+            let on_error = self.policy.fail_mode(name, app);
+            match outcome {
+                Some(Ok(Ok(findings))) => verdict.findings.extend(findings),
+                Some(Ok(Err(payload))) => verdict.fail(name, FailureKind::Error, panic_message(payload), on_error),
+                Some(Err(_)) => verdict.fail(name, FailureKind::Error, "compute pool dropped the job".to_string(), on_error),
+                None => {
+                    let message = format!("no result within {}ms", started.elapsed().as_millis());
+                    verdict.fail(name, FailureKind::Timeout, message, on_error);
+                }
+            }
+        }
+        verdict.finish()
+    }
+}
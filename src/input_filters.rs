@@ -1,14 +1,9 @@
 use regex::Regex;
-use async_std::task;
 use std::error::Error;
 use log::*;
-use rayon::prelude::*;
-use std::sync::{Arc, Mutex};
-use dotenv::dotenv;
-use std::env;
-use crate::db::Database;
-use std::sync::mpsc;
-use std::thread;
+use std::sync::Arc;
+use crate::compute::ComputePool;
+use crate::db::store::PatternStore;
 
 
 /// # Validate Input
@@ -20,32 +15,38 @@ pub struct InputFilter {
     pub description: String,
 }
 
-pub async fn validate_input_concurrently(input:&str, filters: Vec<InputFilter>, db: &Database) -> Vec<String> {
-    let (tx, rx) = mpsc::channel();
-    let patterns = db.fetch_input_patterns().await?;
+/// Checks the input against the stored input patterns and fails on the first match.
+pub async fn validate_input(input: &str, store: &dyn PatternStore) -> Result<(), Box<dyn Error>> {
+    let patterns = store.fetch_input_patterns().await.map_err(|e| e.to_string())?;
 
-    filters.into_par_iter().for_each_with(tx |s, filter| {
-        let re = Regex::new(&filter.pattern).unwrap();
-        if re.is_match(input) {
-            s.send(format!("{} triggered", filter.name, filter.description)).unwrap()
+    for pattern in patterns {
+        if pattern.regex.is_match(input) {
+            warn!("Input validation failed: {} ({})", pattern.name, pattern.description);
+            return Err(Box::new(std::io::Error::new(std::io::ErrorKind::Other, "Disallowed token found.")));
         }
-    });
-
-    rx.iter().collect()
+    }
 
+    Ok(())
 }
 
-/// If environment variable is set to run live, validate streaming data.
-pub fn live_input_validation(stream: &str) {
-    if let Ok(run_live) = env::var("RUN_LIVE") {
-        if run_live == "True" {
-            // Run the stream validation logic
-            println!("Running live input validation for stream: {}", stream);
-            // Use async stream processing if needed.
-        }
+/// Runs the given filters plus the stored input patterns in parallel on the
+/// compute pool and returns a message for each one that matched, filters
+/// first and in the order given.
+pub async fn validate_input_concurrently(input: &str, filters: Vec<InputFilter>, store: &dyn PatternStore, compute: &ComputePool) -> Result<Vec<String>, Box<dyn Error>> {
+    let mut compiled = Vec::new();
+    for filter in filters {
+        compiled.push((filter.name, Regex::new(&filter.pattern)?, filter.description));
+    }
+    for pattern in store.fetch_input_patterns().await.map_err(|e| e.to_string())? {
+        compiled.push((pattern.name, pattern.regex, pattern.description));
     }
-}
-
 
+    let input: Arc<str> = Arc::from(input);
+    let matches = compute
+        .map(compiled, move |(name, re, description)| {
+            re.is_match(&input).then(|| format!("{} triggered: {}", name, description))
+        })
+        .await;
 
- 
\ No newline at end of file
+    Ok(matches.into_iter().flatten().collect())
+}
@@ -0,0 +1,120 @@
+use chrono::{SecondsFormat, Utc};
+use log::kv::{self, Key, Value, VisitSource};
+use log::{LevelFilter, Record};
+use serde_json::{Map, Value as Json};
+use sha2::{Digest, Sha256};
+use std::io::Write;
+use std::sync::OnceLock;
+
+use crate::config::LoggingConfig;
+
+static CONTENT: OnceLock<ContentMode> = OnceLock::new();
+
+/// How message text appears in logs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ContentMode {
+    /// A short sha256 prefix and the length, enough to correlate repeats.
+    Hash,
+    /// The first `n` characters.
+    Truncate(usize),
+    /// The whole message. Only for debugging with non-production data.
+    Full,
+}
+
+impl ContentMode {
+    pub fn parse(value: &str, truncate_chars: usize) -> Option<ContentMode> {
+        match value {
+            "hash" => Some(ContentMode::Hash),
+            "truncate" => Some(ContentMode::Truncate(truncate_chars)),
+            "full" => Some(ContentMode::Full),
+            _ => None,
+        }
+    }
+
+    pub fn apply(&self, text: &str) -> String {
+        match self {
+            ContentMode::Hash => {
+                let digest: String = Sha256::digest(text.as_bytes()).iter().take(8).map(|b| format!("{:02x}", b)).collect();
+                format!("sha256:{} ({} bytes)", digest, text.len())
+            }
+            ContentMode::Truncate(limit) => match text.char_indices().nth(*limit) {
+                Some((end, _)) => format!("{}... ({} bytes)", &text[..end], text.len()),
+                None => text.to_string(),
+            },
+            ContentMode::Full => text.to_string(),
+        }
+    }
+}
+
+/// Message text as it may appear in a log line, per `logging.content`.
+/// Hashed unless `init` chose otherwise.
+pub fn content(text: &str) -> String {
+    CONTENT.get().copied().unwrap_or(ContentMode::Hash).apply(text)
+}
+
+/// Copies a record's key-values into the JSON line.
+struct Fields<'a>(&'a mut Map<String, Json>);
+
+impl<'kvs> VisitSource<'kvs> for Fields<'_> {
+    fn visit_pair(&mut self, key: Key<'kvs>, value: Value<'kvs>) -> Result<(), kv::Error> {
+        let json = if let Some(b) = value.to_bool() {
+            Json::from(b)
+        } else if let Some(n) = value.to_u64() {
+            Json::from(n)
+        } else if let Some(n) = value.to_i64() {
+            Json::from(n)
+        } else {
+            Json::from(value.to_string())
+        };
+        self.0.insert(key.as_str().to_string(), json);
+        Ok(())
+    }
+}
+
+/// Appends a record's key-values as ` key=value`.
+struct Pairs(String);
+
+impl<'kvs> VisitSource<'kvs> for Pairs {
+    fn visit_pair(&mut self, key: Key<'kvs>, value: Value<'kvs>) -> Result<(), kv::Error> {
+        self.0.push_str(&format!(" {}={}", key, value));
+        Ok(())
+    }
+}
+
+pub fn json_line(record: &Record) -> String {
+    let mut line = Map::new();
+    line.insert("ts".to_string(), Json::from(Utc::now().to_rfc3339_opts(SecondsFormat::Millis, true)));
+    line.insert("level".to_string(), Json::from(record.level().as_str()));
+    line.insert("target".to_string(), Json::from(record.target()));
+    line.insert("message".to_string(), Json::from(record.args().to_string()));
+    let _ = record.key_values().visit(&mut Fields(&mut line));
+    Json::Object(line).to_string()
+}
+
+pub fn text_line(record: &Record) -> String {
+    let mut pairs = Pairs(String::new());
+    let _ = record.key_values().visit(&mut pairs);
+    format!(
+        "{} {:<5} {}: {}{}",
+        Utc::now().to_rfc3339_opts(SecondsFormat::Millis, true),
+        record.level(),
+        record.target(),
+        record.args(),
+        pairs.0
+    )
+}
+
+/// Installs the logger. `logging.format` picks one JSON object per line or
+/// plain text; both carry the key-values attached to a log call, such as
+/// `request_id`.
+pub fn init(level: LevelFilter, config: &LoggingConfig) {
+    let _ = CONTENT.set(config.content_mode());
+    let json = config.format == "json";
+    env_logger::Builder::new()
+        .filter_level(level)
+        .format(move |buf, record| {
+            let line = if json { json_line(record) } else { text_line(record) };
+            writeln!(buf, "{}", line)
+        })
+        .init();
+}
@@ -0,0 +1,198 @@
+use log::{info, warn};
+use prometheus::{Encoder, HistogramOpts, HistogramVec, IntCounter, IntCounterVec, IntGauge, Opts, Registry, TextEncoder};
+use std::error::Error;
+use std::sync::{Arc, OnceLock};
+use std::time::Duration;
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::net::{TcpListener, TcpStream};
+
+use crate::findings::{Direction, Finding};
+use crate::reputation::ReputationTracker;
+
+static METRICS: OnceLock<Metrics> = OnceLock::new();
+
+/// Buckets for detector and pipeline latency, from 100µs to 5s.
+const LATENCY_BUCKETS: &[f64] = &[0.0001, 0.0005, 0.001, 0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0];
+
+/// Everything the validator exports, registered once per process.
+pub struct Metrics {
+    registry: Registry,
+    pub findings: IntCounterVec,
+    pub messages: IntCounterVec,
+    pub detector_failures: IntCounterVec,
+    pub detector_duration: HistogramVec,
+    pub pipeline_duration: HistogramVec,
+    pub queue_depth: IntGauge,
+    pub queue_rejected: IntCounter,
+    pub pattern_loads: IntCounterVec,
+    /// Only the `nlp_analysis` helpers observe this; no detector runs a
+    /// model yet, so it stays empty under `serve` and `stream`.
+    pub model_batch_size: HistogramVec,
+    pub reputation_escalations: IntCounterVec,
+    pub reputation_rejections: IntCounterVec,
+}
+
+impl Metrics {
+    fn new() -> Result<Metrics, prometheus::Error> {
+        let registry = Registry::new_custom(Some("llm_validator".to_string()), None)?;
+        let metrics = Metrics {
+            findings: IntCounterVec::new(
+                Opts::new("findings_total", "Findings by rule, the detector that raised them and direction"),
+                &["rule", "detector", "direction"],
+            )?,
+            messages: IntCounterVec::new(
+                Opts::new("messages_total", "Validated messages by pipeline, direction and verdict"),
+                &["pipeline", "direction", "verdict"],
+            )?,
+            detector_failures: IntCounterVec::new(
+                Opts::new("detector_failures_total", "Detectors that timed out or failed, by kind"),
+                &["detector", "kind"],
+            )?,
+            detector_duration: HistogramVec::new(
+                HistogramOpts::new("detector_duration_seconds", "Time one detector spent on one message").buckets(LATENCY_BUCKETS.to_vec()),
+                &["detector"],
+            )?,
+            pipeline_duration: HistogramVec::new(
+                HistogramOpts::new("pipeline_duration_seconds", "Time from receiving a message to its verdict").buckets(LATENCY_BUCKETS.to_vec()),
+                &["pipeline"],
+            )?,
+            queue_depth: IntGauge::new("queue_depth", "Messages waiting for a validator in serve")?,
+            queue_rejected: IntCounter::new("queue_rejected_total", "Messages turned away because the queue was full")?,
+            pattern_loads: IntCounterVec::new(
+                Opts::new("pattern_loads_total", "Pattern loads from the store when a guard is built, by outcome"),
+                &["detector", "result"],
+            )?,
+            model_batch_size: HistogramVec::new(
+                HistogramOpts::new("model_batch_size", "Texts per model inference call").buckets(vec![1.0, 2.0, 4.0, 8.0, 16.0, 32.0, 64.0, 128.0]),
+                &["model"],
+            )?,
+            reputation_escalations: IntCounterVec::new(
+                Opts::new("reputation_escalations_total", "Clients moved up to warn, throttle or block by their reputation"),
+                &["level"],
+            )?,
+            reputation_rejections: IntCounterVec::new(
+                Opts::new("reputation_rejections_total", "Messages turned away because their client was throttled or blocked"),
+                &["level"],
+            )?,
+            registry,
+        };
+
+        metrics.registry.register(Box::new(metrics.findings.clone()))?;
+        metrics.registry.register(Box::new(metrics.messages.clone()))?;
+        metrics.registry.register(Box::new(metrics.detector_failures.clone()))?;
+        metrics.registry.register(Box::new(metrics.detector_duration.clone()))?;
+        metrics.registry.register(Box::new(metrics.pipeline_duration.clone()))?;
+        metrics.registry.register(Box::new(metrics.queue_depth.clone()))?;
+        metrics.registry.register(Box::new(metrics.queue_rejected.clone()))?;
+        metrics.registry.register(Box::new(metrics.pattern_loads.clone()))?;
+        metrics.registry.register(Box::new(metrics.model_batch_size.clone()))?;
+        metrics.registry.register(Box::new(metrics.reputation_escalations.clone()))?;
+        metrics.registry.register(Box::new(metrics.reputation_rejections.clone()))?;
+        Ok(metrics)
+    }
+
+    /// Prometheus text exposition of every metric.
+    pub fn render(&self) -> String {
+        let mut buffer = Vec::new();
+        if let Err(e) = TextEncoder::new().encode(&self.registry.gather(), &mut buffer) {
+            warn!("Failed to encode metrics: {}", e);
+        }
+        String::from_utf8(buffer).unwrap_or_default()
+    }
+}
+
+pub fn metrics() -> &'static Metrics {
+    METRICS.get_or_init(|| Metrics::new().expect("metric definitions are valid"))
+}
+
+/// Records one detector run over one message.
+pub fn record_detector(detector: &str, direction: Direction, elapsed: Duration, findings: &[Finding]) {
+    let metrics = metrics();
+    metrics.detector_duration.with_label_values(&[detector]).observe(elapsed.as_secs_f64());
+    for finding in findings {
+        metrics.findings.with_label_values(&[&finding.rule, detector, direction.as_str()]).inc();
+    }
+}
+
+/// Records the verdict a pipeline (`serve`, `stream`, `check`, `scan`) reached
+/// for one message and how long it took.
+pub fn record_verdict(pipeline: &str, direction: Direction, blocked: bool, elapsed: Duration) {
+    let metrics = metrics();
+    let verdict = if blocked { "blocked" } else { "allowed" };
+    metrics.messages.with_label_values(&[pipeline, direction.as_str(), verdict]).inc();
+    metrics.pipeline_duration.with_label_values(&[pipeline]).observe(elapsed.as_secs_f64());
+}
+
+fn http_response(status: &str, content_type: &str, body: &str) -> String {
+    format!(
+        "HTTP/1.1 {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status,
+        content_type,
+        body.len(),
+        body
+    )
+}
+
+/// `GET /reputation`, `GET /reputation/<client>` and
+/// `DELETE /reputation/<client>`, the last lifting any throttle or block.
+fn reputation_response(method: &str, path: &str, tracker: Option<&ReputationTracker>) -> Option<String> {
+    let tracker = tracker?;
+    let client_id = match path.strip_prefix("/reputation") {
+        Some("") | Some("/") => None,
+        Some(rest) => Some(rest.strip_prefix('/')?),
+        None => return None,
+    };
+    let not_found = || http_response("404 Not Found", "application/json", "{\"error\":\"unknown client\"}");
+    let json = |body: serde_json::Result<String>| http_response("200 OK", "application/json", &body.unwrap_or_default());
+    Some(match (method, client_id) {
+        ("GET", None) => json(serde_json::to_string(&tracker.list())),
+        ("GET", Some(id)) => match tracker.inspect(id) {
+            Some(client) => json(serde_json::to_string(&client)),
+            None => not_found(),
+        },
+        ("DELETE", Some(id)) => match tracker.reset(id) {
+            true => {
+                info!("Reset the reputation of {}", id);
+                http_response("204 No Content", "application/json", "")
+            }
+            false => not_found(),
+        },
+        _ => http_response("405 Method Not Allowed", "text/plain", ""),
+    })
+}
+
+async fn handle_scrape(socket: TcpStream, reputation: Option<Arc<ReputationTracker>>) -> Result<(), Box<dyn Error>> {
+    let (reader, mut writer) = socket.into_split();
+    let mut lines = BufReader::new(reader).lines();
+    let request_line = lines.next_line().await?.unwrap_or_default();
+    // Skip the headers; scrapes have no body.
+    while let Some(line) = lines.next_line().await? {
+        if line.is_empty() {
+            break;
+        }
+    }
+
+    let response = match request_line.split_whitespace().take(2).collect::<Vec<_>>()[..] {
+        ["GET", "/metrics"] => http_response("200 OK", "text/plain; version=0.0.4", &metrics().render()),
+        [method, path] => reputation_response(method, path, reputation.as_deref()).unwrap_or_else(|| http_response("404 Not Found", "text/plain", "")),
+        _ => http_response("404 Not Found", "text/plain", ""),
+    };
+    writer.write_all(response.as_bytes()).await?;
+    Ok(())
+}
+
+/// Answers `GET /metrics` on `listen` until the task is dropped, and the
+/// `/reputation` routes when a tracker is given.
+pub async fn serve_metrics(listen: String, reputation: Option<Arc<ReputationTracker>>) -> Result<(), Box<dyn Error + Send + Sync>> {
+    let listener = TcpListener::bind(&listen).await?;
+    info!("Serving metrics on http://{}/metrics", listen);
+    loop {
+        let (socket, peer) = listener.accept().await?;
+        let reputation = reputation.clone();
+        tokio::spawn(async move {
+            if let Err(e) = handle_scrape(socket, reputation).await {
+                warn!("Metrics scrape from {} failed: {}", peer, e);
+            }
+        });
+    }
+}
@@ -1,40 +1,63 @@
 use tokenizers::tokenizer::{Tokenizer, Encoding};
-use rust_bert::pipelines::sentiment::SentimentModel;
+use rust_bert::pipelines::sentiment::{SentimentConfig, SentimentModel};
+use rust_bert::resources::LocalResource;
 use rust_bert::pipelines::sequence_classification::SequenceClassificationModel;
-use std::env;
+use std::path::Path;
+use log::debug;
+use crate::config::ModelConfig;
+use crate::metrics;
 
-pub fn analyze_text(text: &str) -> Result<Encoding, Box<dyn std::error::Error>> {
-    // Load a tokenizer (e.g., BERT tokenizer)
-    let tokenizer = Tokenizer::from_pretrained("bert-base-uncased", None)?;
+pub fn analyze_text(text: &str, models: &ModelConfig) -> Result<Encoding, Box<dyn std::error::Error>> {
+    let _span = tracing::info_span!("model", model = "tokenizer", name = models.tokenizer.as_str()).entered();
+    // Load the configured tokenizer, either a local tokenizer.json or a pretrained name
+    let tokenizer = if Path::new(&models.tokenizer).is_file() {
+        Tokenizer::from_file(&models.tokenizer)?
+    } else {
+        Tokenizer::from_pretrained(&models.tokenizer, None)?
+    };
     
     // Encode the text input
+    metrics::metrics().model_batch_size.with_label_values(&["tokenizer"]).observe(1.0);
     let encoding = tokenizer.encode(text, true)?;
     Ok(encoding)
 }
 // Sentiment and Toxicity
-pub fn analyze_sentiment(text: &str) -> Result<(), Box<dyn std::error::Error>> {
-    let model = SentimentModel::new(Default::default())?;
+pub fn analyze_sentiment(text: &str, models: &ModelConfig) -> Result<(), Box<dyn std::error::Error>> {
+    let _span = tracing::info_span!("model", model = "sentiment").entered();
+    // A local model directory holds the files rust-bert would otherwise download
+    let config = match &models.sentiment_model {
+        Some(dir) => SentimentConfig {
+            model_resource: Box::new(LocalResource::from(dir.join("rust_model.ot"))),
+            config_resource: Box::new(LocalResource::from(dir.join("config.json"))),
+            vocab_resource: Box::new(LocalResource::from(dir.join("vocab.txt"))),
+            ..Default::default()
+        },
+        None => Default::default(),
+    };
+    let model = SentimentModel::new(config)?;
     
     // Predict sentiment on the text
-    let sentiments = model.predict(&[text]);
-    println!("Sentiment: {:?}", sentiments);
+    let batch = [text];
+    metrics::metrics().model_batch_size.with_label_values(&["sentiment"]).observe(batch.len() as f64);
+    let sentiments = model.predict(&batch);
+    debug!("Sentiment: {:?}", sentiments);
 
     Ok(())
 }
 
 //Prompt Injection
-pub fn detect_prompt_injection(text: &str) -> bool {
-    let prompt_patterns = vec![
-        "ignore previous instructions",
-        "pretend you are",
-        "please execute",
-        "run this code",
-        "system: ",
-    ];
+pub const PROMPT_INJECTION_PHRASES: &[&str] = &[
+    "ignore previous instructions",
+    "pretend you are",
+    "please execute",
+    "run this code",
+    "system: ",
+];
 
-    for pattern in prompt_patterns {
+pub fn detect_prompt_injection(text: &str) -> bool {
+    for pattern in PROMPT_INJECTION_PHRASES {
         if text.contains(pattern) {
-            println!("Potential prompt injection detected: {}", pattern);
+            debug!("Potential prompt injection detected: {}", pattern);
             return true;
         }
     }
@@ -42,58 +65,43 @@ pub fn detect_prompt_injection(text: &str) -> bool {
     false
 }
 
-// If streaming enabled
-pub fn run_analysis_live(text: &str) {
-    if env::var("RUN_LIVE").unwrap_or("false".into()) == "true" {
-        println!("Running live analysis...");
-        analyze_text(text).unwrap();
-        analyze_sentiment(text).unwrap();
-    } else {
-        println!("Running file-based analysis...");
-    }
-}
-
 pub mod input_validator {
+    use log::{info, warn};
     use super::analyze_text;
     use super::detect_prompt_injection;
+    use crate::config::ModelConfig;
+use crate::metrics;
 
-    pub fn validate_input(text: &str) {
+    pub fn validate_input(text: &str, models: &ModelConfig) {
         // Tokenization & basic analysis
-        analyze_text(text).unwrap();
+        analyze_text(text, models).unwrap();
         
         // Detect prompt injection
         if detect_prompt_injection(text) {
-            println!("Alert: Prompt Injection detected in input.");
+            warn!("Prompt injection detected in input");
         } else {
-            println!("Input is safe.");
+            info!("Input is safe");
         }
     }
 }
 
 pub mod output_validator {
+    use log::{info, warn};
     use super::analyze_sentiment;
     use super::detect_prompt_injection;
+    use crate::config::ModelConfig;
+use crate::metrics;
 
-    pub fn validate_output(text: &str) {
+    pub fn validate_output(text: &str, models: &ModelConfig) {
         // Sentiment & toxicity analysis
-        analyze_sentiment(text).unwrap();
+        analyze_sentiment(text, models).unwrap();
         
         // Detect prompt injection
         if detect_prompt_injection(text) {
-            println!("Alert: Prompt Injection detected in output.");
+            warn!("Prompt injection detected in output");
         } else {
-            println!("Output is safe.");
+            info!("Output is safe");
         }
     }
 }
 
-//Integrate DB
-pub fn store_input_validation(db: &PgConnection, text: &str, prompt_injection: bool) {
-    diesel::insert_into(input_validations::table)
-        .values((
-            input_validations::text.eq(text),
-            input_validations::prompt_injection.eq(prompt_injection),
-        ))
-        .execute(db)
-        .expect("Error saving input validation");
-}
\ No newline at end of file
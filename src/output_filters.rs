@@ -1,18 +1,16 @@
-use regex::Regex;
-use std::sync::{ Arc, Mutex };
-use rayon::prelude::*;
-use crate::db::Database;
+use std::error::Error;
+use crate::db::store::PatternStore;
 
 /// Checks for sensitive data like social security numbers, API keys, etc.
 /// 
 /// 
-pub async fn validate_output(output: &str, db: &Database) -> Result<(), Box<dyn Error>> {
-    let patterns = db.fetch_output_patterns().await?;
+pub async fn validate_output(output: &str, store: &dyn PatternStore) -> Result<(), Box<dyn Error>> {
+    let patterns = store.fetch_output_patterns().await.map_err(|e| e.to_string())?;
     
-    for (pattern, description) in patterns {
-        if pattern.is_match(output) {
-            println!("Output validation failed: {}", description);
-            return Err(Box::new(std::io::Error::new(std::io::ErrorKind::Other, description)));
+    for pattern in patterns {
+        if pattern.regex.is_match(output) {
+            log::warn!("Output validation failed: {} ({})", pattern.name, pattern.description);
+            return Err(Box::new(std::io::Error::new(std::io::ErrorKind::Other, pattern.description)));
         }
     }
     
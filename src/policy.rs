@@ -0,0 +1,191 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::fs;
+use std::path::Path;
+use std::time::{Duration, Instant};
+use tracing::{field, info_span};
+
+use crate::detectors::Detector;
+use crate::findings::{Direction, Finding};
+use crate::metrics;
+
+/// Threshold used for detectors the policy doesn't mention. A single finding
+/// scores 0.5, so by default any finding flags the message.
+pub const DEFAULT_THRESHOLD: f32 = 0.5;
+
+/// What happens to a message when a detector can't give an answer for it,
+/// because it timed out, failed, or the input was over the size cap.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FailMode {
+    /// Let the message through and record the failure.
+    Open,
+    /// Block the message.
+    #[default]
+    Closed,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DetectorLimits {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub timeout_ms: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub on_error: Option<FailMode>,
+}
+
+/// Deadlines and size caps. Anything unset is unlimited, and failures block
+/// unless `on_error` says otherwise.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Limits {
+    /// Larger messages fail closed, or are validated only up to the cap when
+    /// failing open.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_input_bytes: Option<usize>,
+    /// Time allowed for all detectors together on one message.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub request_budget_ms: Option<u64>,
+    /// Time allowed for each detector unless `detectors` says otherwise.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub detector_timeout_ms: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub on_error: Option<FailMode>,
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub detectors: BTreeMap<String, DetectorLimits>,
+}
+
+impl Limits {
+    fn is_empty(&self) -> bool {
+        *self == Limits::default()
+    }
+}
+
+/// Decision settings loaded from a JSON policy file.
+///
+/// Keys this version doesn't know about are kept in `extra` and written back
+/// untouched, so tools that update one section don't drop the others.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Policy {
+    /// Score at or above which a detector flags a message.
+    #[serde(default)]
+    pub thresholds: BTreeMap<String, f32>,
+    #[serde(default, skip_serializing_if = "Limits::is_empty")]
+    pub limits: Limits,
+    /// Per-app overrides of `limits`, keyed by the app a message came from.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub apps: BTreeMap<String, Limits>,
+    #[serde(flatten)]
+    pub extra: serde_json::Map<String, serde_json::Value>,
+}
+
+impl Policy {
+    pub fn load(path: &str) -> Result<Policy, Box<dyn Error>> {
+        let content = fs::read_to_string(path)?;
+        Ok(serde_json::from_str(&content).map_err(|e| format!("{}: {}", path, e))?)
+    }
+
+    /// Loads the policy if the file exists, otherwise starts from defaults.
+    pub fn load_or_default(path: &str) -> Result<Policy, Box<dyn Error>> {
+        if Path::new(path).exists() {
+            Policy::load(path)
+        } else {
+            Ok(Policy::default())
+        }
+    }
+
+    pub fn save(&self, path: &str) -> Result<(), Box<dyn Error>> {
+        fs::write(path, serde_json::to_string_pretty(self)? + "\n")?;
+        Ok(())
+    }
+
+    pub fn threshold_for(&self, detector: &str) -> f32 {
+        self.thresholds.get(detector).copied().unwrap_or(DEFAULT_THRESHOLD)
+    }
+
+    /// The limits that apply to a message, most specific first.
+    fn layers(&self, app: Option<&str>) -> Vec<&Limits> {
+        app.and_then(|app| self.apps.get(app)).into_iter().chain(Some(&self.limits)).collect()
+    }
+
+    /// Looks for a per-detector setting in the app's limits and then the
+    /// global ones, before falling back to their general `on_error`.
+    pub fn fail_mode(&self, detector: &str, app: Option<&str>) -> FailMode {
+        let layers = self.layers(app);
+        layers
+            .iter()
+            .find_map(|l| l.detectors.get(detector).and_then(|d| d.on_error))
+            .or_else(|| layers.iter().find_map(|l| l.on_error))
+            .unwrap_or_default()
+    }
+
+    /// Fail mode for messages over `max_input_bytes`.
+    pub fn input_fail_mode(&self, app: Option<&str>) -> FailMode {
+        self.layers(app).iter().find_map(|l| l.on_error).unwrap_or_default()
+    }
+
+    pub fn detector_timeout(&self, detector: &str, app: Option<&str>) -> Option<Duration> {
+        let layers = self.layers(app);
+        layers
+            .iter()
+            .find_map(|l| l.detectors.get(detector).and_then(|d| d.timeout_ms))
+            .or_else(|| layers.iter().find_map(|l| l.detector_timeout_ms))
+            .map(Duration::from_millis)
+    }
+
+    pub fn request_budget(&self, app: Option<&str>) -> Option<Duration> {
+        self.layers(app).iter().find_map(|l| l.request_budget_ms).map(Duration::from_millis)
+    }
+
+    pub fn max_input_bytes(&self, app: Option<&str>) -> Option<usize> {
+        self.layers(app).iter().find_map(|l| l.max_input_bytes)
+    }
+
+    /// The largest `max_input_bytes` any app ends up with, or `None` if some
+    /// message can be of any size.
+    pub fn largest_input_bytes(&self) -> Option<usize> {
+        let global = self.limits.max_input_bytes;
+        let caps = self.apps.values().map(|l| l.max_input_bytes.or(global));
+        Some(global).into_iter().chain(caps).collect::<Option<Vec<usize>>>().and_then(|caps| caps.into_iter().max())
+    }
+
+    /// One detector's findings if its score crosses its threshold, recorded
+    /// in the detector metrics and a `detector` span.
+    pub fn detector_findings(&self, detector: &dyn Detector, text: &str, direction: Direction) -> Vec<Finding> {
+        if !detector.applies_to(direction) {
+            return Vec::new();
+        }
+        let span = info_span!(
+            "detector",
+            detector = detector.name(),
+            direction = direction.as_str(),
+            score = field::Empty,
+            threshold = field::Empty,
+            rules = field::Empty,
+        );
+        let _entered = span.enter();
+
+        let started = Instant::now();
+        let mut findings = detector.detect(text, direction);
+        let score = detector.score_findings(&findings);
+        let threshold = self.threshold_for(detector.name());
+        if score < threshold {
+            findings.clear();
+        }
+        metrics::record_detector(detector.name(), direction, started.elapsed(), &findings);
+
+        span.record("score", score as f64);
+        span.record("threshold", threshold as f64);
+        if !findings.is_empty() {
+            let rules: Vec<&str> = findings.iter().map(|f| f.rule.as_str()).collect();
+            span.record("rules", rules.join(",").as_str());
+        }
+        findings
+    }
+
+    /// Findings from every detector whose score crosses its threshold.
+    pub fn findings(&self, detectors: &[Box<dyn Detector>], text: &str, direction: Direction) -> Vec<Finding> {
+        detectors.iter().flat_map(|d| self.detector_findings(d.as_ref(), text, direction)).collect()
+    }
+}
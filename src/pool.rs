@@ -0,0 +1,264 @@
+use log::{info, warn};
+use std::error::Error;
+use std::fmt;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::mpsc::error::TrySendError;
+use tokio::sync::{mpsc, oneshot, watch, Mutex};
+use tokio::task::JoinHandle;
+use tracing::{Instrument, Span};
+
+use crate::conversation::Conversations;
+use crate::documents::{self, Document, DocumentVerdict};
+use crate::findings::Direction;
+use crate::guard::{Guard, Verdict};
+use crate::metrics;
+
+/// What `submit` does when every queue slot is taken.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Overflow {
+    /// Wait for a free slot, pushing back on the caller.
+    Wait,
+    /// Fail straight away with [`PoolError::QueueFull`].
+    Reject,
+}
+
+impl Overflow {
+    pub fn parse(value: &str) -> Option<Overflow> {
+        match value {
+            "wait" => Some(Overflow::Wait),
+            "reject" => Some(Overflow::Reject),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Overflow::Wait => "wait",
+            Overflow::Reject => "reject",
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct PoolConfig {
+    /// Tasks pulling messages off the queue.
+    pub workers: usize,
+    /// Messages that can wait for a worker before `overflow` kicks in.
+    pub queue_depth: usize,
+    pub overflow: Overflow,
+}
+
+impl Default for PoolConfig {
+    fn default() -> Self {
+        PoolConfig {
+            workers: std::thread::available_parallelism().map(|n| n.get()).unwrap_or(1),
+            queue_depth: 1024,
+            overflow: Overflow::Wait,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PoolError {
+    /// The queue was full and the pool rejects rather than waits.
+    QueueFull,
+    /// The pool is shutting down and takes no new messages.
+    Closed,
+}
+
+impl fmt::Display for PoolError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            PoolError::QueueFull => write!(f, "validation queue is full"),
+            PoolError::Closed => write!(f, "validator is shutting down"),
+        }
+    }
+}
+
+impl Error for PoolError {}
+
+enum Work {
+    Message {
+        text: String,
+        direction: Direction,
+        /// Checks the message as a turn of this conversation.
+        session_id: Option<String>,
+        reply: oneshot::Sender<Verdict>,
+    },
+    Document {
+        document: Document,
+        reply: oneshot::Sender<DocumentVerdict>,
+    },
+}
+
+struct Job {
+    work: Work,
+    app: Option<String>,
+    /// The submitter's span, so the detector spans nest under it.
+    span: Span,
+}
+
+/// Cheap to clone; one per connection or caller. Each submitted message gets
+/// its verdict back on its own reply channel.
+#[derive(Clone)]
+pub struct PoolHandle {
+    sender: mpsc::Sender<Job>,
+    overflow: Overflow,
+    queue_depth: usize,
+}
+
+impl PoolHandle {
+    pub async fn submit(&self, text: String, direction: Direction, app: Option<String>) -> Result<Verdict, PoolError> {
+        self.submit_turn(None, text, direction, app).await
+    }
+
+    /// Like `submit`, but checks the message as a turn of `session_id` when
+    /// the pool tracks conversations.
+    pub async fn submit_turn(&self, session_id: Option<String>, text: String, direction: Direction, app: Option<String>) -> Result<Verdict, PoolError> {
+        let (reply, verdict) = oneshot::channel();
+        let work = Work::Message {
+            text,
+            direction,
+            session_id,
+            reply,
+        };
+        self.enqueue(work, app).await?;
+        verdict.await.map_err(|_| PoolError::Closed)
+    }
+
+    /// Checks a retrieved document, scanning it as well as running the
+    /// detectors off the caller's task.
+    pub async fn submit_document(&self, document: Document, app: Option<String>) -> Result<DocumentVerdict, PoolError> {
+        let (reply, verdict) = oneshot::channel();
+        self.enqueue(Work::Document { document, reply }, app).await?;
+        verdict.await.map_err(|_| PoolError::Closed)
+    }
+
+    async fn enqueue(&self, work: Work, app: Option<String>) -> Result<(), PoolError> {
+        let job = Job {
+            work,
+            app,
+            span: Span::current(),
+        };
+        match self.overflow {
+            Overflow::Wait => self.sender.send(job).await.map_err(|_| PoolError::Closed)?,
+            Overflow::Reject => self.sender.try_send(job).map_err(|e| match e {
+                TrySendError::Full(_) => {
+                    metrics::metrics().queue_rejected.inc();
+                    PoolError::QueueFull
+                }
+                TrySendError::Closed(_) => PoolError::Closed,
+            })?,
+        }
+        metrics::metrics().queue_depth.inc();
+        Ok(())
+    }
+
+    /// Messages waiting for a worker.
+    pub fn queued(&self) -> usize {
+        self.queue_depth - self.sender.capacity()
+    }
+}
+
+/// A fixed set of tasks taking messages from one bounded queue. The guard
+/// runs the detectors on its compute pool, so a worker waiting for a verdict
+/// leaves its tokio thread free.
+pub struct WorkerPool {
+    handle: PoolHandle,
+    stopping: watch::Sender<bool>,
+    workers: Vec<JoinHandle<()>>,
+}
+
+impl WorkerPool {
+    pub fn spawn(guard: Arc<Guard>, config: PoolConfig) -> WorkerPool {
+        WorkerPool::spawn_with_conversations(guard, None, config)
+    }
+
+    /// A pool that checks messages submitted with a session id as turns of
+    /// that conversation.
+    pub fn spawn_with_conversations(guard: Arc<Guard>, conversations: Option<Arc<Conversations>>, config: PoolConfig) -> WorkerPool {
+        let (sender, receiver) = mpsc::channel(config.queue_depth);
+        let receiver = Arc::new(Mutex::new(receiver));
+        let (stopping, watcher) = watch::channel(false);
+
+        let workers = (0..config.workers.max(1))
+            .map(|_| tokio::spawn(run_worker(guard.clone(), conversations.clone(), receiver.clone(), watcher.clone())))
+            .collect();
+        let handle = PoolHandle {
+            sender,
+            overflow: config.overflow,
+            queue_depth: config.queue_depth,
+        };
+        WorkerPool { handle, stopping, workers }
+    }
+
+    pub fn handle(&self) -> PoolHandle {
+        self.handle.clone()
+    }
+
+    /// Stops taking new messages, lets the workers finish everything already
+    /// queued and waits for them. Returns false if `timeout` ran out first;
+    /// messages still queued then are dropped and their callers see
+    /// [`PoolError::Closed`].
+    pub async fn shutdown(self, timeout: Duration) -> bool {
+        info!("Draining {} queued messages", self.handle.queued());
+        let _ = self.stopping.send(true);
+
+        let workers = self.workers;
+        let drained = tokio::time::timeout(timeout, async {
+            for worker in workers {
+                let _ = worker.await;
+            }
+        })
+        .await;
+        if drained.is_err() {
+            warn!("Workers did not drain the queue within {:?}", timeout);
+        }
+        drained.is_ok()
+    }
+}
+
+async fn run_worker(guard: Arc<Guard>, conversations: Option<Arc<Conversations>>, receiver: Arc<Mutex<mpsc::Receiver<Job>>>, mut stopping: watch::Receiver<bool>) {
+    loop {
+        let job = {
+            let mut receiver = receiver.lock().await;
+            tokio::select! {
+                biased;
+                job = receiver.recv() => job,
+                _ = stopping.changed() => {
+                    // Closing keeps what is already queued, so the loop
+                    // drains it before `recv` returns None.
+                    receiver.close();
+                    receiver.recv().await
+                }
+            }
+        };
+
+        match job {
+            Some(job) => {
+                metrics::metrics().queue_depth.dec();
+                let app = job.app.as_deref();
+                // The caller may have gone away; its verdict is simply dropped.
+                match job.work {
+                    Work::Message {
+                        text,
+                        direction,
+                        session_id,
+                        reply,
+                    } => {
+                        let verdict = match (&conversations, &session_id) {
+                            (Some(conversations), Some(session_id)) => conversations.check(&guard, session_id, &text, direction, app).instrument(job.span).await,
+                            _ => guard.check_message(&text, direction, app).instrument(job.span).await,
+                        };
+                        let _ = reply.send(verdict);
+                    }
+                    Work::Document { document, reply } => {
+                        let _ = reply.send(documents::check_document(&guard, document, app).instrument(job.span).await);
+                    }
+                }
+            }
+            None => break,
+        }
+    }
+}
@@ -0,0 +1,330 @@
+use clap::Args;
+use serde::Serialize;
+use serde_json::json;
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::fs;
+use std::sync::Arc;
+use std::time::Instant;
+
+use crate::cli::DetectionArgs;
+use crate::config::Config;
+use crate::db::audit::{AuditWriter, ValidationRecord};
+use crate::db::store::PatternStore;
+use crate::db::Database;
+use crate::findings::{Direction, Finding};
+use crate::guard::Guard;
+use crate::metrics;
+
+/// Process exit code when a run produced blocking findings, so CI can tell
+/// "the validator failed" (1) apart from "the validator blocked something".
+pub const EXIT_BLOCKED: i32 = 2;
+
+const TOOL_NAME: &str = "llm_validator";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReportFormat {
+    Text,
+    Json,
+    Sarif,
+    Junit,
+    Html,
+}
+
+impl ReportFormat {
+    pub fn parse(name: &str) -> Result<ReportFormat, String> {
+        match name.to_lowercase().as_str() {
+            "text" => Ok(ReportFormat::Text),
+            "json" => Ok(ReportFormat::Json),
+            "sarif" => Ok(ReportFormat::Sarif),
+            "junit" => Ok(ReportFormat::Junit),
+            "html" => Ok(ReportFormat::Html),
+            other => Err(format!("unknown report format '{}', expected text, json, sarif, junit or html", other)),
+        }
+    }
+}
+
+/// One validated text: a file, or one side of a conversation record.
+#[derive(Debug, Clone, Serialize)]
+pub struct ReportEntry {
+    /// Where the text came from, e.g. a path. Used as the SARIF artifact URI
+    /// and the JUnit test case name.
+    pub source: String,
+    pub direction: Direction,
+    pub text: String,
+    pub findings: Vec<Finding>,
+}
+
+impl ReportEntry {
+    pub fn blocked(&self) -> bool {
+        !self.findings.is_empty()
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct Report {
+    pub entries: Vec<ReportEntry>,
+}
+
+impl Report {
+    /// Checks `text` with the guard and adds the entry with the findings of
+    /// its verdict.
+    pub async fn check(&mut self, source: &str, text: String, direction: Direction, guard: &Guard) {
+        let started = Instant::now();
+        let verdict = guard.check_message(&text, direction, None).await;
+        metrics::record_verdict("check", direction, verdict.blocked, started.elapsed());
+        self.entries.push(ReportEntry {
+            source: source.to_string(),
+            direction,
+            text,
+            findings: verdict.findings,
+        });
+    }
+
+    pub fn blocked(&self) -> bool {
+        self.entries.iter().any(ReportEntry::blocked)
+    }
+
+    pub fn finding_count(&self) -> usize {
+        self.entries.iter().map(|e| e.findings.len()).sum()
+    }
+
+    pub fn render(&self, format: ReportFormat) -> Result<String, Box<dyn Error>> {
+        Ok(match format {
+            ReportFormat::Text => self.to_text(),
+            ReportFormat::Json => serde_json::to_string_pretty(self)? + "\n",
+            ReportFormat::Sarif => serde_json::to_string_pretty(&self.to_sarif())? + "\n",
+            ReportFormat::Junit => self.to_junit(),
+            ReportFormat::Html => self.to_html(),
+        })
+    }
+
+    fn to_text(&self) -> String {
+        let mut out = String::new();
+        for entry in &self.entries {
+            let status = if entry.blocked() { "BLOCKED" } else { "ok" };
+            out += &format!("{} ({}): {}\n", entry.source, entry.direction.as_str(), status);
+            for finding in &entry.findings {
+                let (line, column) = line_and_column(&entry.text, finding.start);
+                out += &format!("  {}:{} {}: {}\n", line, column, finding.rule, finding.description);
+            }
+        }
+        out
+    }
+
+    /// SARIF 2.1.0, one run with a rule per distinct finding rule.
+    pub fn to_sarif(&self) -> serde_json::Value {
+        let mut rules = BTreeMap::new();
+        for finding in self.entries.iter().flat_map(|e| &e.findings) {
+            rules.entry(finding.rule.clone()).or_insert_with(|| finding.description.clone());
+        }
+        let rules: Vec<_> = rules
+            .iter()
+            .map(|(rule, description)| json!({ "id": rule, "name": rule, "shortDescription": { "text": description } }))
+            .collect();
+
+        let mut results = Vec::new();
+        for entry in &self.entries {
+            for finding in &entry.findings {
+                let (line, column) = line_and_column(&entry.text, finding.start);
+                results.push(json!({
+                    "ruleId": finding.rule,
+                    "level": "error",
+                    "message": { "text": format!("{} ({})", finding.description, finding.direction.as_str()) },
+                    "locations": [{
+                        "physicalLocation": {
+                            "artifactLocation": { "uri": entry.source },
+                            "region": {
+                                "startLine": line,
+                                "startColumn": column,
+                                "byteOffset": finding.start,
+                                "byteLength": finding.end.saturating_sub(finding.start),
+                            }
+                        }
+                    }]
+                }));
+            }
+        }
+
+        json!({
+            "$schema": "https://json.schemastore.org/sarif-2.1.0.json",
+            "version": "2.1.0",
+            "runs": [{
+                "tool": { "driver": { "name": TOOL_NAME, "version": env!("CARGO_PKG_VERSION"), "rules": rules } },
+                "results": results,
+            }]
+        })
+    }
+
+    /// JUnit XML with one test case per entry, failing when it has findings.
+    pub fn to_junit(&self) -> String {
+        let failures = self.entries.iter().filter(|e| e.blocked()).count();
+        let mut out = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+        out += &format!("<testsuites tests=\"{}\" failures=\"{}\">\n", self.entries.len(), failures);
+        out += &format!("  <testsuite name=\"{}\" tests=\"{}\" failures=\"{}\">\n", TOOL_NAME, self.entries.len(), failures);
+        for entry in &self.entries {
+            out += &format!("    <testcase classname=\"{}\" name=\"{}\"", entry.direction.as_str(), escape_xml(&entry.source));
+            if !entry.blocked() {
+                out += "/>\n";
+                continue;
+            }
+            out += ">\n";
+            for finding in &entry.findings {
+                let (line, column) = line_and_column(&entry.text, finding.start);
+                out += &format!(
+                    "      <failure type=\"{}\" message=\"{}\">{}:{}:{}</failure>\n",
+                    escape_xml(&finding.rule),
+                    escape_xml(&finding.description),
+                    escape_xml(&entry.source),
+                    line,
+                    column
+                );
+            }
+            out += "    </testcase>\n";
+        }
+        out += "  </testsuite>\n</testsuites>\n";
+        out
+    }
+
+    /// A self-contained HTML page with each matched span highlighted.
+    pub fn to_html(&self) -> String {
+        let mut out = String::from(concat!(
+            "<!DOCTYPE html>\n<html><head><meta charset=\"utf-8\"><title>Validation report</title>\n<style>\n",
+            "body { font-family: sans-serif; margin: 2em; }\n",
+            "pre { white-space: pre-wrap; background: #f6f8fa; padding: 1em; }\n",
+            "mark { background: #ffd33d; }\n",
+            ".blocked { color: #cb2431; } .ok { color: #22863a; }\n",
+            "</style></head><body>\n",
+        ));
+        out += &format!(
+            "<h1>Validation report</h1>\n<p>{} entries, {} findings</p>\n",
+            self.entries.len(),
+            self.finding_count()
+        );
+        for entry in &self.entries {
+            let (class, status) = if entry.blocked() { ("blocked", "blocked") } else { ("ok", "passed") };
+            out += &format!(
+                "<h2>{} <small>({})</small> <span class=\"{}\">{}</span></h2>\n",
+                escape_xml(&entry.source),
+                entry.direction.as_str(),
+                class,
+                status
+            );
+            if entry.blocked() {
+                out += "<ul>\n";
+                for finding in &entry.findings {
+                    out += &format!("<li><b>{}</b>: {}</li>\n", escape_xml(&finding.rule), escape_xml(&finding.description));
+                }
+                out += "</ul>\n";
+            }
+            out += &format!("<pre>{}</pre>\n", highlight(&entry.text, &entry.findings));
+        }
+        out += "</body></html>\n";
+        out
+    }
+}
+
+/// 1-based line and column (in characters) of a byte offset.
+fn line_and_column(text: &str, offset: usize) -> (usize, usize) {
+    let mut offset = offset.min(text.len());
+    while !text.is_char_boundary(offset) {
+        offset -= 1;
+    }
+    let before = &text[..offset];
+    let line = before.matches('\n').count() + 1;
+    let column = before.rsplit('\n').next().unwrap_or("").chars().count() + 1;
+    (line, column)
+}
+
+pub fn escape_xml(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&#39;"),
+            _ => out.push(c),
+        }
+    }
+    out
+}
+
+/// Escapes `text` and wraps each finding's span in `<mark>`. Overlapping
+/// spans are merged into the earlier one; spans that don't fall on character
+/// boundaries are left unmarked rather than splitting a character.
+fn highlight(text: &str, findings: &[Finding]) -> String {
+    let mut spans: Vec<&Finding> = findings
+        .iter()
+        .filter(|f| f.start < f.end && f.end <= text.len() && text.is_char_boundary(f.start) && text.is_char_boundary(f.end))
+        .collect();
+    spans.sort_by_key(|f| (f.start, f.end));
+
+    let mut out = String::new();
+    let mut cursor = 0;
+    for finding in spans {
+        if finding.end <= cursor {
+            continue;
+        }
+        let start = finding.start.max(cursor);
+        out += &escape_xml(&text[cursor..start]);
+        out += &format!("<mark title=\"{}\">{}</mark>", escape_xml(&finding.rule), escape_xml(&text[start..finding.end]));
+        cursor = finding.end;
+    }
+    out += &escape_xml(&text[cursor..]);
+    out
+}
+
+#[derive(Debug, Args)]
+pub struct CheckArgs {
+    /// Prompt sent to the model.
+    #[arg(default_value = "input.txt")]
+    pub input: String,
+
+    /// Response that came back.
+    #[arg(default_value = "output.txt")]
+    pub output: String,
+
+    /// text, json, sarif, junit or html.
+    #[arg(long, env = "LLM_VALIDATOR_FORMAT", default_value = "text", value_parser = ReportFormat::parse)]
+    pub format: ReportFormat,
+
+    /// Write the report here instead of stdout.
+    #[arg(long)]
+    pub out: Option<String>,
+
+    #[command(flatten)]
+    pub detection: DetectionArgs,
+}
+
+/// Handles `check [input_file] [output_file] [--format F] [--out FILE] [--detectors a,b] [--policy FILE]`.
+///
+/// Validates the input file as a prompt and the output file as a model
+/// response and renders the report. With `sinks.audit` enabled both checks
+/// are recorded in the audit trail under their file name, which needs `db`.
+/// Returns whether anything was blocked so the caller can exit with
+/// `EXIT_BLOCKED`.
+pub async fn run_check_command(args: &CheckArgs, config: &Config, db: Option<&Database>, store: Arc<dyn PatternStore>) -> Result<bool, Box<dyn Error>> {
+    let guard = config.detection.guard(store, config.workers.compute_pool()?).await?;
+    let audit = AuditWriter::from_config(&config.sinks.audit, db);
+    let mut report = Report::default();
+    for (path, direction) in [(&args.input, Direction::Input), (&args.output, Direction::Output)] {
+        let started = Instant::now();
+        report.check(path, fs::read_to_string(path)?, direction, &guard).await;
+        if let (Some((writer, _)), Some(entry)) = (&audit, report.entries.last()) {
+            writer.record(ValidationRecord::new(direction, entry.findings.clone(), started.elapsed()).for_request(path, None));
+        }
+    }
+    if let Some((writer, task)) = audit {
+        writer.finish(task).await;
+    }
+
+    let rendered = report.render(args.format)?;
+    match &args.out {
+        Some(path) => fs::write(path, rendered)?,
+        None => print!("{}", rendered),
+    }
+
+    Ok(report.blocked())
+}
@@ -0,0 +1,374 @@
+use chrono::{DateTime, Utc};
+use log::warn;
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::task::JoinHandle;
+
+use crate::config::ReputationConfig;
+use crate::db::store::StoreError;
+use crate::db::{Database, DbError};
+use crate::findings::Finding;
+use crate::metrics;
+
+/// How hard a client is pushed back, from least to most.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Escalation {
+    Allow,
+    /// Messages are still validated; the client is logged.
+    Warn,
+    /// The client gets `throttle_per_minute` messages a minute.
+    Throttle,
+    /// Every message is turned away until the block expires.
+    Block,
+}
+
+impl Escalation {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Escalation::Allow => "allow",
+            Escalation::Warn => "warn",
+            Escalation::Throttle => "throttle",
+            Escalation::Block => "block",
+        }
+    }
+}
+
+/// What the tracker knows about one user or client.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ClientReputation {
+    pub client_id: String,
+    /// Risk as of `updated_at`; it halves every `half_life`.
+    pub risk: f64,
+    pub updated_at: DateTime<Utc>,
+    pub blocked_until: Option<DateTime<Utc>>,
+    /// Blocked messages over the client's lifetime.
+    pub offenses: u64,
+}
+
+impl ClientReputation {
+    pub fn new(client_id: &str, now: DateTime<Utc>) -> Self {
+        ClientReputation {
+            client_id: client_id.to_string(),
+            risk: 0.0,
+            updated_at: now,
+            blocked_until: None,
+            offenses: 0,
+        }
+    }
+
+    pub fn risk_at(&self, now: DateTime<Utc>, half_life: Duration) -> f64 {
+        let elapsed = (now - self.updated_at).to_std().unwrap_or_default();
+        if half_life.is_zero() {
+            return 0.0;
+        }
+        self.risk * 0.5f64.powf(elapsed.as_secs_f64() / half_life.as_secs_f64())
+    }
+
+    fn blocked_at(&self, now: DateTime<Utc>) -> Option<Duration> {
+        self.blocked_until.filter(|until| *until > now).map(|until| (until - now).to_std().unwrap_or_default())
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ReputationSettings {
+    pub half_life: Duration,
+    pub warn_at: f64,
+    pub throttle_at: f64,
+    pub block_at: f64,
+    pub block_for: Duration,
+    pub throttle_per_minute: u32,
+    /// Risk a blocked message adds per rule it matched; 1 for rules not
+    /// listed.
+    pub weights: BTreeMap<String, f64>,
+}
+
+impl Default for ReputationSettings {
+    fn default() -> Self {
+        ReputationSettings {
+            half_life: Duration::from_secs(600),
+            warn_at: 3.0,
+            throttle_at: 6.0,
+            block_at: 10.0,
+            block_for: Duration::from_secs(900),
+            throttle_per_minute: 6,
+            weights: BTreeMap::new(),
+        }
+    }
+}
+
+/// Whether a client's next message is validated, and if not, when it can
+/// try again.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Admission {
+    pub level: Escalation,
+    pub retry_after: Option<Duration>,
+}
+
+impl Admission {
+    pub fn is_allowed(&self) -> bool {
+        self.retry_after.is_none()
+    }
+
+    /// The error a turned-away client gets back.
+    pub fn message(&self) -> String {
+        let secs = self.retry_after.unwrap_or_default().as_secs().max(1);
+        match self.level {
+            Escalation::Block => format!("client is blocked for another {}s after repeated attacks", secs),
+            _ => format!("client is throttled after repeated attacks, retry in {}s", secs),
+        }
+    }
+}
+
+/// Characters of a client id kept, as many as `client_reputation.client_id`
+/// holds. Longer ids are cut rather than rejected, so a client can't dodge
+/// its reputation by padding its id.
+const MAX_CLIENT_ID_CHARS: usize = 255;
+
+fn client_key(client_id: &str) -> &str {
+    match client_id.char_indices().nth(MAX_CLIENT_ID_CHARS) {
+        Some((end, _)) => &client_id[..end],
+        None => client_id,
+    }
+}
+
+#[derive(Default)]
+struct Clients {
+    clients: HashMap<String, ClientReputation>,
+    last_admitted: HashMap<String, DateTime<Utc>>,
+    /// Changed since the last flush to the database.
+    dirty: HashSet<String>,
+    reset: HashSet<String>,
+}
+
+/// Tracks how often each client trips the detectors and escalates from
+/// warning to throttling to blocking as its risk builds up. State lives in
+/// memory; [`flush`](Self::flush) copies it to Postgres.
+pub struct ReputationTracker {
+    settings: ReputationSettings,
+    state: Mutex<Clients>,
+}
+
+impl ReputationTracker {
+    pub fn new(settings: ReputationSettings) -> Self {
+        ReputationTracker {
+            settings,
+            state: Mutex::new(Clients::default()),
+        }
+    }
+
+    /// From `[reputation]`, or `None` when it is off. The database store
+    /// loads the saved state from `db` and starts a task saving changes
+    /// every `flush_interval_secs`.
+    pub async fn from_config(config: &ReputationConfig, db: Option<&Database>) -> Result<Option<(Arc<ReputationTracker>, Option<JoinHandle<()>>)>, StoreError> {
+        if !config.enabled {
+            return Ok(None);
+        }
+        let tracker = Arc::new(ReputationTracker::new(config.settings()));
+        let flusher = match config.store.as_str() {
+            "database" => {
+                let db = db.ok_or("reputation.store = \"database\" needs database.url")?;
+                tracker.load(db.fetch_reputations().await?);
+                Some(tracker.clone().spawn_flusher(db.clone(), Duration::from_secs(config.flush_interval_secs)))
+            }
+            _ => None,
+        };
+        Ok(Some((tracker, flusher)))
+    }
+
+    pub fn load(&self, clients: Vec<ClientReputation>) {
+        let mut state = self.state.lock().unwrap();
+        for client in clients {
+            state.clients.insert(client.client_id.clone(), client);
+        }
+    }
+
+    fn level(&self, risk: f64) -> Escalation {
+        if risk >= self.settings.block_at {
+            Escalation::Block
+        } else if risk >= self.settings.throttle_at {
+            Escalation::Throttle
+        } else if risk >= self.settings.warn_at {
+            Escalation::Warn
+        } else {
+            Escalation::Allow
+        }
+    }
+
+    pub fn admit(&self, client_id: &str) -> Admission {
+        self.admit_at(client_id, Utc::now())
+    }
+
+    /// Decides on a client's next message before it is validated.
+    pub fn admit_at(&self, client_id: &str, now: DateTime<Utc>) -> Admission {
+        let client_id = client_key(client_id);
+        let mut state = self.state.lock().unwrap();
+        let client = match state.clients.get(client_id) {
+            Some(client) => client,
+            None => {
+                return Admission {
+                    level: Escalation::Allow,
+                    retry_after: None,
+                }
+            }
+        };
+        if let Some(remaining) = client.blocked_at(now) {
+            metrics::metrics().reputation_rejections.with_label_values(&["block"]).inc();
+            return Admission {
+                level: Escalation::Block,
+                retry_after: Some(remaining),
+            };
+        }
+
+        let level = self.level(client.risk_at(now, self.settings.half_life));
+        if level < Escalation::Throttle {
+            return Admission { level, retry_after: None };
+        }
+        // Past the threshold but no longer blocked: throttled until the risk
+        // decays.
+        let spacing = Duration::from_secs(60) / self.settings.throttle_per_minute.max(1);
+        if let Some(last) = state.last_admitted.get(client_id) {
+            let since = (now - *last).to_std().unwrap_or_default();
+            if since < spacing {
+                metrics::metrics().reputation_rejections.with_label_values(&["throttle"]).inc();
+                return Admission {
+                    level: Escalation::Throttle,
+                    retry_after: Some(spacing - since),
+                };
+            }
+        }
+        state.last_admitted.insert(client_id.to_string(), now);
+        Admission {
+            level: Escalation::Throttle,
+            retry_after: None,
+        }
+    }
+
+    pub fn record(&self, client_id: &str, findings: &[Finding]) -> Escalation {
+        self.record_at(client_id, findings, Utc::now())
+    }
+
+    /// Adds a validated message's findings to the client's risk and returns
+    /// the level it is at afterwards. Reaching the block level starts a block
+    /// of `block_for`.
+    pub fn record_at(&self, client_id: &str, findings: &[Finding], now: DateTime<Utc>) -> Escalation {
+        let client_id = client_key(client_id);
+        let half_life = self.settings.half_life;
+        let mut state = self.state.lock().unwrap();
+        if findings.is_empty() {
+            return match state.clients.get(client_id) {
+                Some(client) => self.level(client.risk_at(now, half_life)),
+                None => Escalation::Allow,
+            };
+        }
+
+        if state.clients.len() >= 10_000 {
+            // Forget clients whose risk has all but decayed away.
+            let Clients { clients, last_admitted, .. } = &mut *state;
+            clients.retain(|id, c| {
+                let keep = c.blocked_at(now).is_some() || c.risk_at(now, half_life) >= 0.01;
+                if !keep {
+                    last_admitted.remove(id);
+                }
+                keep
+            });
+        }
+        let rules: HashSet<&str> = findings.iter().map(|f| f.rule.as_str()).collect();
+        let weight: f64 = rules.iter().map(|rule| self.settings.weights.get(*rule).copied().unwrap_or(1.0)).sum();
+
+        let client = state.clients.entry(client_id.to_string()).or_insert_with(|| ClientReputation::new(client_id, now));
+        let before = self.level(client.risk_at(now, half_life));
+        client.risk = client.risk_at(now, half_life) + weight;
+        client.updated_at = now;
+        client.offenses += 1;
+        let level = self.level(client.risk);
+        if level == Escalation::Block && client.blocked_at(now).is_none() {
+            client.blocked_until = Some(now + chrono::Duration::from_std(self.settings.block_for).unwrap_or_else(|_| chrono::Duration::zero()));
+        }
+        if level > before {
+            metrics::metrics().reputation_escalations.with_label_values(&[level.as_str()]).inc();
+        }
+        state.dirty.insert(client_id.to_string());
+        state.reset.remove(client_id);
+        level
+    }
+
+    /// A client's state with its risk decayed to now.
+    pub fn inspect(&self, client_id: &str) -> Option<ClientReputation> {
+        let client_id = client_key(client_id);
+        let now = Utc::now();
+        let state = self.state.lock().unwrap();
+        state.clients.get(client_id).map(|client| ClientReputation {
+            risk: client.risk_at(now, self.settings.half_life),
+            updated_at: now,
+            ..client.clone()
+        })
+    }
+
+    /// Every tracked client, riskiest first.
+    pub fn list(&self) -> Vec<ClientReputation> {
+        let now = Utc::now();
+        let state = self.state.lock().unwrap();
+        let mut clients: Vec<ClientReputation> = state
+            .clients
+            .values()
+            .map(|client| ClientReputation {
+                risk: client.risk_at(now, self.settings.half_life),
+                updated_at: now,
+                ..client.clone()
+            })
+            .collect();
+        clients.sort_by(|a, b| b.risk.total_cmp(&a.risk));
+        clients
+    }
+
+    /// Forgets a client, lifting any throttle or block. Returns whether it
+    /// was tracked.
+    pub fn reset(&self, client_id: &str) -> bool {
+        let client_id = client_key(client_id);
+        let mut state = self.state.lock().unwrap();
+        state.last_admitted.remove(client_id);
+        state.dirty.remove(client_id);
+        state.reset.insert(client_id.to_string());
+        state.clients.remove(client_id).is_some()
+    }
+
+    /// Saves what changed since the last flush. If saving fails the changes
+    /// are kept for the next flush.
+    pub async fn flush(&self, db: &Database) -> Result<(), DbError> {
+        let (dirty, changed, reset) = {
+            let mut state = self.state.lock().unwrap();
+            let dirty = std::mem::take(&mut state.dirty);
+            let changed: Vec<ClientReputation> = dirty.iter().filter_map(|id| state.clients.get(id).cloned()).collect();
+            let reset = std::mem::take(&mut state.reset);
+            (dirty, changed, reset)
+        };
+        let saved = async {
+            for client_id in &reset {
+                db.delete_reputation(client_id).await?;
+            }
+            db.save_reputations(&changed).await
+        }
+        .await;
+        if saved.is_err() {
+            let mut state = self.state.lock().unwrap();
+            state.dirty.extend(dirty);
+            state.reset.extend(reset);
+        }
+        saved
+    }
+
+    fn spawn_flusher(self: Arc<Self>, db: Database, interval: Duration) -> JoinHandle<()> {
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(interval);
+            loop {
+                ticker.tick().await;
+                if let Err(e) = self.flush(&db).await {
+                    warn!("Failed to save client reputations: {}", e);
+                }
+            }
+        })
+    }
+}
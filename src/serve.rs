@@ -0,0 +1,330 @@
+use clap::Args;
+use log::{debug, info, warn};
+use serde::Serialize;
+use std::error::Error;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::io::{AsyncWriteExt, BufReader};
+use tokio::net::{TcpListener, TcpStream};
+use tracing::{field, info_span, Instrument};
+use tracing_opentelemetry::OpenTelemetrySpanExt;
+use uuid::Uuid;
+
+use crate::alerts::{self, AlertHandle};
+use crate::cli::DetectionArgs;
+use crate::config::Config;
+use crate::conversation::Conversations;
+use crate::db::audit::{AuditWriter, ValidationRecord};
+use crate::db::store::PatternStore;
+use crate::db::Database;
+use crate::documents::{self, DocumentBatch};
+use crate::findings::Direction;
+use crate::logging;
+use crate::metrics;
+use crate::pool::{PoolHandle, WorkerPool};
+use crate::reputation::{Escalation, ReputationTracker};
+use crate::sources::{self, BoundedLines, Message};
+use crate::telemetry;
+
+#[derive(Debug, Args)]
+pub struct ServeArgs {
+    /// Address to accept connections on.
+    #[arg(long)]
+    pub listen: Option<String>,
+
+    /// Messages validated at once; 0 uses one per CPU.
+    #[arg(long)]
+    pub validators: Option<usize>,
+
+    /// Messages that can wait for a validator.
+    #[arg(long)]
+    pub queue_depth: Option<usize>,
+
+    /// Address to serve Prometheus metrics on, at `/metrics`.
+    #[arg(long)]
+    pub metrics_listen: Option<String>,
+
+    #[command(flatten)]
+    pub detection: DetectionArgs,
+}
+
+/// Sent instead of a verdict when a message couldn't be validated.
+#[derive(Debug, Serialize)]
+struct Rejection {
+    error: String,
+    /// Set when the client is throttled or blocked by its reputation.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    retry_after_secs: Option<u64>,
+}
+
+async fn handle_connection(
+    socket: TcpStream,
+    peer: SocketAddr,
+    pool: &PoolHandle,
+    max_line_bytes: usize,
+    audit: Option<&AuditWriter>,
+    alerts: Option<&AlertHandle>,
+    reputation: Option<&ReputationTracker>,
+) -> Result<(), Box<dyn Error>> {
+    let (reader, mut writer) = socket.into_split();
+    let mut lines = BoundedLines::new(BufReader::new(reader), max_line_bytes);
+
+    while let Some(line) = lines.next_line().await? {
+        let message = match line {
+            Ok(line) => Message::from_line(line),
+            Err(e) => {
+                warn!(client_id = peer.ip().to_string().as_str(); "Rejected message: {}", e);
+                let mut response = serde_json::to_string(&Rejection {
+                    error: e.to_string(),
+                    retry_after_secs: None,
+                })?;
+                response.push('\n');
+                writer.write_all(response.as_bytes()).await?;
+                continue;
+            }
+        };
+        // Plain text lines and JSON without a direction are prompts.
+        let direction = message.direction.unwrap_or(Direction::Input);
+        let started = Instant::now();
+        let request_id = message.request_id.clone().unwrap_or_else(|| Uuid::new_v4().to_string());
+        let app = message.app.clone().unwrap_or_default();
+        let content = logging::content(&message.text);
+        let client_id = message.client_id.clone().unwrap_or_else(|| peer.ip().to_string());
+        if let Some(admission) = reputation.map(|r| r.admit(&client_id)).filter(|a| !a.is_allowed()) {
+            warn!(request_id = request_id.as_str(), client_id = client_id.as_str(), level = admission.level.as_str(); "Turned away message");
+            let rejection = Rejection {
+                error: admission.message(),
+                retry_after_secs: admission.retry_after.map(|d| d.as_secs().max(1)),
+            };
+            let mut response = serde_json::to_string(&rejection)?;
+            response.push('\n');
+            writer.write_all(response.as_bytes()).await?;
+            continue;
+        }
+        let span = info_span!(
+            "validate",
+            pipeline = "serve",
+            request_id = request_id.as_str(),
+            direction = direction.as_str(),
+            app = app.as_str(),
+            session_id = message.session_id.as_deref().unwrap_or(""),
+            client_id = client_id.as_str(),
+            blocked = field::Empty,
+            findings = field::Empty,
+        );
+        span.set_parent(telemetry::extract_context(message.traceparent.as_deref(), message.tracestate.as_deref()));
+        if !message.documents.is_empty() {
+            // Kept only for alerts, the pool takes the documents.
+            let alert_texts: Option<Vec<String>> = alerts.map(|_| message.documents.iter().map(|d| d.text.clone()).collect());
+            let alert_app = message.app.clone();
+            let checked = documents::check_on_pool(pool, message.documents, message.app).instrument(span.clone()).await;
+            let mut response = match checked {
+                Ok(verdicts) => {
+                    let batch = DocumentBatch::new(verdicts);
+                    span.record("blocked", batch.blocked);
+                    batch.log_blocked(&request_id);
+                    if let (Some(alerts), Some(texts)) = (alerts, &alert_texts) {
+                        for (document, text) in batch.documents.iter().zip(texts) {
+                            alerts.raise(&document.as_verdict(), text, &request_id, alert_app.as_deref());
+                        }
+                    }
+                    if let Some(audit) = audit {
+                        for document in &batch.documents {
+                            let record = ValidationRecord::new(Direction::Input, document.findings.clone(), started.elapsed());
+                            audit.record(record.for_request(&request_id, alert_app.as_deref()));
+                        }
+                    }
+                    serde_json::to_string(&batch)?
+                }
+                Err(e) => {
+                    warn!(request_id = request_id.as_str(), app = app.as_str(); "Could not validate documents: {}", e);
+                    if let Some(audit) = audit {
+                        audit.record(ValidationRecord::failed(Direction::Input, started.elapsed()).for_request(&request_id, alert_app.as_deref()));
+                    }
+                    serde_json::to_string(&Rejection {
+                        error: e.to_string(),
+                        retry_after_secs: None,
+                    })?
+                }
+            };
+            response.push('\n');
+            writer.write_all(response.as_bytes()).await?;
+            continue;
+        }
+        // Kept only for alerts, the pool takes the text.
+        let alert_text = alerts.map(|_| message.text.clone());
+        let alert_app = message.app.clone();
+
+        let submitted = pool.submit_turn(message.session_id, message.text, direction, message.app).instrument(span.clone()).await;
+        let mut response = match submitted {
+            Ok(verdict) => {
+                span.record("blocked", verdict.blocked);
+                span.record("findings", verdict.findings.len());
+                metrics::record_verdict("serve", direction, verdict.blocked, started.elapsed());
+                if verdict.blocked {
+                    warn!(
+                        request_id = request_id.as_str(),
+                        direction = direction.as_str(),
+                        app = app.as_str(),
+                        findings = verdict.findings.len(),
+                        content = content.as_str();
+                        "Blocked message"
+                    );
+                    if let (Some(alerts), Some(text)) = (alerts, &alert_text) {
+                        alerts.raise(&verdict, text, &request_id, alert_app.as_deref());
+                    }
+                } else {
+                    debug!(request_id = request_id.as_str(), direction = direction.as_str(), app = app.as_str(), content = content.as_str(); "Allowed message");
+                }
+                if let Some(audit) = audit {
+                    let record = ValidationRecord::new(direction, verdict.findings.clone(), started.elapsed());
+                    audit.record(record.for_request(&request_id, alert_app.as_deref()));
+                }
+                if let Some(reputation) = reputation {
+                    let level = reputation.record(&client_id, &verdict.findings);
+                    if verdict.blocked && level >= Escalation::Warn {
+                        warn!(request_id = request_id.as_str(), client_id = client_id.as_str(), level = level.as_str(); "Client keeps sending blocked messages");
+                    }
+                }
+                serde_json::to_string(&verdict)?
+            }
+            Err(e) => {
+                warn!(request_id = request_id.as_str(), app = app.as_str(); "Could not validate message: {}", e);
+                if let Some(audit) = audit {
+                    audit.record(ValidationRecord::failed(direction, started.elapsed()).for_request(&request_id, alert_app.as_deref()));
+                }
+                serde_json::to_string(&Rejection {
+                    error: e.to_string(),
+                    retry_after_secs: None,
+                })?
+            }
+        };
+        response.push('\n');
+        writer.write_all(response.as_bytes()).await?;
+    }
+
+    Ok(())
+}
+
+/// Resolves on Ctrl-C, or on SIGTERM where there is one.
+pub async fn shutdown_signal() -> std::io::Result<()> {
+    #[cfg(unix)]
+    {
+        let mut terminate = tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate())?;
+        tokio::select! {
+            result = tokio::signal::ctrl_c() => result,
+            _ = terminate.recv() => Ok(()),
+        }
+    }
+    #[cfg(not(unix))]
+    tokio::signal::ctrl_c().await
+}
+
+/// Handles `serve [--listen ADDR] [--validators N] [--queue-depth N] [--detectors a,b] [--policy FILE]`.
+///
+/// Clients send one message per line, either plain text or
+/// `{"direction": "output", "text": "...", "app": "chat"}`, and get one JSON verdict line
+/// back. Lines too long for any app's `max_input_bytes` are skipped and
+/// answered with an error. A `traceparent` field makes the message's spans part of the
+/// caller's trace. Messages go through a bounded worker pool, so a burst either waits
+/// or is rejected depending on `workers.when_full`. Verdicts go to the audit
+/// trail when `sinks.audit` is enabled, and blocks raise alerts to the sinks
+/// under `[sinks.alerts]`. Messages with a `session_id` are checked as turns
+/// of that conversation when `conversations.enabled` is set. Messages with
+/// `documents` instead of `text` get a verdict per retrieved document, each
+/// audited and alerted on like a message. With
+/// `reputation.enabled`, clients (`client_id`, or the peer address) that keep
+/// sending blocked messages are throttled and then blocked for a while; the
+/// metrics listener serves `/reputation` to inspect and reset them. `db` is
+/// needed for the audit trail and the database session and reputation
+/// stores. Runs until SIGTERM or Ctrl-C, then drains the queue before
+/// returning.
+pub async fn run_serve_command(config: &Config, db: Option<&Database>, store: Arc<dyn PatternStore>) -> Result<(), Box<dyn Error>> {
+    let guard = Arc::new(config.detection.guard(store, config.workers.compute_pool()?).await?);
+    let max_line_bytes = sources::max_line_bytes(guard.policy());
+    let pool_config = config.workers.pool_config();
+    info!("Starting {} validators, queue depth {}, {} when full", pool_config.workers, pool_config.queue_depth, pool_config.overflow.as_str());
+    let conversations = Conversations::from_config(&config.conversations, db)?;
+    let pool = WorkerPool::spawn_with_conversations(guard, conversations, pool_config);
+    let (audit, audit_task) = match AuditWriter::from_config(&config.sinks.audit, db) {
+        Some((writer, task)) => (Some(writer), Some(task)),
+        None => (None, None),
+    };
+    let (alerts, alerts_task) = match alerts::start(&config.sinks.alerts).await? {
+        Some((handle, task)) => (Some(handle), Some(task)),
+        None => (None, None),
+    };
+    let (reputation, reputation_task) = match ReputationTracker::from_config(&config.reputation, db).await? {
+        Some((tracker, task)) => (Some(tracker), task),
+        None => (None, None),
+    };
+
+    let metrics_reputation = reputation.clone();
+    let metrics_task = config.server.metrics_listen.clone().map(|listen| {
+        tokio::spawn(async move {
+            if let Err(e) = metrics::serve_metrics(listen, metrics_reputation).await {
+                warn!("Metrics endpoint stopped: {}", e);
+            }
+        })
+    });
+
+    let listener = TcpListener::bind(&config.server.listen).await?;
+    info!("Listening on {}", config.server.listen);
+
+    let shutdown = shutdown_signal();
+    tokio::pin!(shutdown);
+    loop {
+        tokio::select! {
+            accepted = listener.accept() => {
+                let (socket, peer) = accepted?;
+                let handle = pool.handle();
+                let audit = audit.clone();
+                let alerts = alerts.clone();
+                let reputation = reputation.clone();
+                tokio::spawn(async move {
+                    if let Err(e) = handle_connection(socket, peer, &handle, max_line_bytes, audit.as_ref(), alerts.as_ref(), reputation.as_deref()).await {
+                        warn!("Connection from {} failed: {}", peer, e);
+                    }
+                });
+            }
+            result = &mut shutdown => {
+                result?;
+                break;
+            }
+        }
+    }
+
+    drop(listener);
+    let timeout = Duration::from_secs(config.server.shutdown_timeout_secs);
+    info!("Shutting down, no longer accepting connections");
+    pool.shutdown(timeout).await;
+    if let Some(task) = metrics_task {
+        task.abort();
+    }
+
+    if let (Some(reputation), Some(task), Some(db)) = (&reputation, reputation_task, db) {
+        task.abort();
+        if let Err(e) = reputation.flush(db).await {
+            warn!("Failed to save client reputations: {}", e);
+        }
+    }
+
+    drop(audit);
+    if let Some(task) = audit_task {
+        info!("Flushing the audit trail");
+        if tokio::time::timeout(timeout, task).await.is_err() {
+            warn!("Audit writer did not finish within {:?}; open connections still hold it", timeout);
+        }
+    }
+
+    drop(alerts);
+    if let Some(task) = alerts_task {
+        info!("Delivering queued alerts");
+        if tokio::time::timeout(timeout, task).await.is_err() {
+            warn!("Alert sinks did not finish within {:?}", timeout);
+        }
+    }
+
+    Ok(())
+}
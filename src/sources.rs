@@ -0,0 +1,468 @@
+use async_trait::async_trait;
+use log::{info, warn};
+use serde::Deserialize;
+use std::error::Error;
+use std::fmt;
+use std::io;
+use std::path::PathBuf;
+use std::time::Duration;
+use tokio::fs::{File, OpenOptions};
+use tokio::io::{stdin, stdout, AsyncBufRead, AsyncBufReadExt, AsyncRead, AsyncSeekExt, AsyncWrite, BufReader, SeekFrom};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::mpsc;
+
+use crate::config::StreamConfig;
+use crate::documents::Document;
+use crate::findings::Direction;
+use crate::policy::Policy;
+
+pub type SourceError = Box<dyn Error + Send + Sync>;
+
+/// Longest line read when the policy doesn't cap message sizes.
+pub const DEFAULT_MAX_LINE_BYTES: usize = 16 * 1024 * 1024;
+
+/// Room on top of the largest message for the JSON around its text.
+const LINE_MARGIN_BYTES: usize = 64 * 1024;
+
+/// The longest line `serve` and `stream` read: twice the largest
+/// `max_input_bytes`, since JSON escaping can grow the text, plus a margin.
+pub fn max_line_bytes(policy: &Policy) -> usize {
+    match policy.largest_input_bytes() {
+        Some(cap) => cap.saturating_mul(2).saturating_add(LINE_MARGIN_BYTES),
+        None => DEFAULT_MAX_LINE_BYTES,
+    }
+}
+
+/// A line longer than the reader takes. It was skipped, and is answered
+/// with an error instead of a verdict.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LineTooLong {
+    pub limit: usize,
+}
+
+impl fmt::Display for LineTooLong {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "line is longer than {} bytes", self.limit)
+    }
+}
+
+impl Error for LineTooLong {}
+
+/// Splits a reader into lines of at most `max_bytes`, so a client that never
+/// sends a newline can't make us buffer without bound. A longer line is
+/// dropped as it is read, up to its newline, and reported as [`LineTooLong`].
+pub struct BoundedLines<R> {
+    reader: R,
+    max_bytes: usize,
+    line: Vec<u8>,
+    too_long: bool,
+    consumed: u64,
+}
+
+impl<R: AsyncBufRead + Unpin> BoundedLines<R> {
+    pub fn new(reader: R, max_bytes: usize) -> Self {
+        BoundedLines {
+            reader,
+            max_bytes,
+            line: Vec::new(),
+            too_long: false,
+            consumed: 0,
+        }
+    }
+
+    /// Bytes taken from the reader so far.
+    pub fn consumed(&self) -> u64 {
+        self.consumed
+    }
+
+    /// The next line without its line ending, or `None` at the end of the
+    /// input. The last line doesn't need a newline.
+    pub async fn next_line(&mut self) -> io::Result<Option<Result<String, LineTooLong>>> {
+        match self.read().await? {
+            Some(line) => Ok(Some(line)),
+            None if self.line.is_empty() && !self.too_long => Ok(None),
+            None => self.take_line().map(Some),
+        }
+    }
+
+    /// Like [`next_line`](Self::next_line) for a file that may still grow: a
+    /// line without its newline yet is kept for a later call, and `None`
+    /// means there is nothing more for now.
+    pub async fn next_complete_line(&mut self) -> io::Result<Option<Result<String, LineTooLong>>> {
+        self.read().await
+    }
+
+    async fn read(&mut self) -> io::Result<Option<Result<String, LineTooLong>>> {
+        loop {
+            let available = self.reader.fill_buf().await?;
+            if available.is_empty() {
+                return Ok(None);
+            }
+            let newline = available.iter().position(|b| *b == b'\n');
+            let used = newline.map_or(available.len(), |i| i + 1);
+            if !self.too_long {
+                self.line.extend_from_slice(&available[..newline.unwrap_or(used)]);
+                if self.line.len() > self.max_bytes {
+                    self.line = Vec::new();
+                    self.too_long = true;
+                }
+            }
+            self.reader.consume(used);
+            self.consumed += used as u64;
+            if newline.is_some() {
+                return self.take_line().map(Some);
+            }
+        }
+    }
+
+    fn take_line(&mut self) -> io::Result<Result<String, LineTooLong>> {
+        if std::mem::take(&mut self.too_long) {
+            return Ok(Err(LineTooLong { limit: self.max_bytes }));
+        }
+        let mut line = std::mem::take(&mut self.line);
+        if line.ends_with(b"\r") {
+            line.pop();
+        }
+        String::from_utf8(line).map(Ok).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+    }
+}
+
+/// One message read from a source: either an NDJSON object or a plain text
+/// line, which is taken as the whole `text`.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct Message {
+    /// The source's default direction applies when absent.
+    #[serde(default)]
+    pub direction: Option<Direction>,
+    /// Empty for messages that carry `documents` instead.
+    #[serde(default)]
+    pub text: String,
+    /// Selects the app's overrides in the policy limits.
+    #[serde(default)]
+    pub app: Option<String>,
+    /// W3C trace context forwarded by the proxy or API in front of us.
+    #[serde(default)]
+    pub traceparent: Option<String>,
+    #[serde(default)]
+    pub tracestate: Option<String>,
+    /// Correlates our log lines with the caller's; generated when absent.
+    #[serde(default)]
+    pub request_id: Option<String>,
+    /// Checks the message as a turn of this conversation when
+    /// `conversations.enabled` is set.
+    #[serde(default)]
+    pub session_id: Option<String>,
+    /// The user or client whose reputation the message counts towards when
+    /// `reputation.enabled` is set. `serve` falls back to the peer address.
+    #[serde(default)]
+    pub client_id: Option<String>,
+    /// Retrieved documents to check as untrusted context, each getting its
+    /// own verdict.
+    #[serde(default)]
+    pub documents: Vec<Document>,
+}
+
+impl Message {
+    /// A line that looks like JSON but doesn't parse as a message is still
+    /// checked, as plain text, with a warning so a client's broken framing
+    /// doesn't go unnoticed.
+    pub fn from_line(line: String) -> Message {
+        if line.trim_start().starts_with('{') {
+            match serde_json::from_str(&line) {
+                Ok(message) => return message,
+                Err(e) => warn!("Checking a line as plain text, it isn't a valid JSON message: {}", e),
+            }
+        }
+        Message {
+            direction: None,
+            text: line,
+            app: None,
+            traceparent: None,
+            tracestate: None,
+            request_id: None,
+            session_id: None,
+            client_id: None,
+            documents: Vec::new(),
+        }
+    }
+}
+
+/// Where `stream` reads messages from.
+#[async_trait]
+pub trait StreamSource: Send {
+    fn name(&self) -> &str;
+
+    /// The next message, or `None` once the source has ended. Sources that
+    /// listen or follow a file only end on an error. A message over the
+    /// source's size cap comes back as [`LineTooLong`] and the source goes on.
+    async fn next(&mut self) -> Result<Option<Result<Message, LineTooLong>>, SourceError>;
+}
+
+/// A parsed `stream.source` value.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SourceSpec {
+    Stdin,
+    /// Listens for connections sending one message per line.
+    Tcp(String),
+    Unix(PathBuf),
+    /// Follows a growing file, like `tail -F`.
+    File(PathBuf),
+    Kafka { brokers: String, topic: String },
+}
+
+impl SourceSpec {
+    pub fn parse(value: &str) -> Result<SourceSpec, String> {
+        let invalid = || format!("'{}' must be stdin, tcp://host:port, unix:///path, file:///path or kafka://brokers/topic", value);
+        if value == "stdin" || value == "-" {
+            return Ok(SourceSpec::Stdin);
+        }
+        let (scheme, rest) = value.split_once("://").ok_or_else(invalid)?;
+        match scheme {
+            "tcp" if rest.rsplit_once(':').map_or(false, |(host, port)| !host.is_empty() && port.parse::<u16>().is_ok()) => {
+                Ok(SourceSpec::Tcp(rest.to_string()))
+            }
+            "unix" if !rest.is_empty() => Ok(SourceSpec::Unix(PathBuf::from(rest))),
+            "file" if !rest.is_empty() => Ok(SourceSpec::File(PathBuf::from(rest))),
+            "kafka" => match rest.split_once('/') {
+                Some((brokers, topic)) if !brokers.is_empty() && !topic.is_empty() => Ok(SourceSpec::Kafka {
+                    brokers: brokers.to_string(),
+                    topic: topic.to_string(),
+                }),
+                _ => Err(invalid()),
+            },
+            _ => Err(invalid()),
+        }
+    }
+}
+
+/// Lines gathered from stdin or from every connection to a listener.
+pub struct LineSource {
+    name: String,
+    lines: mpsc::Receiver<Result<String, LineTooLong>>,
+}
+
+impl LineSource {
+    fn forward<R: AsyncRead + Unpin + Send + 'static>(reader: R, max_line_bytes: usize, lines: mpsc::Sender<Result<String, LineTooLong>>) {
+        tokio::spawn(async move {
+            let mut reader = BoundedLines::new(BufReader::new(reader), max_line_bytes);
+            loop {
+                match reader.next_line().await {
+                    Ok(Some(Ok(line))) if line.trim().is_empty() => {}
+                    Ok(Some(line)) => {
+                        if lines.send(line).await.is_err() {
+                            break;
+                        }
+                    }
+                    Ok(None) => break,
+                    Err(e) => {
+                        warn!("Stopped reading a stream connection: {}", e);
+                        break;
+                    }
+                }
+            }
+        });
+    }
+
+    /// Ends when stdin closes.
+    pub fn stdin(max_line_bytes: usize) -> LineSource {
+        let (sender, lines) = mpsc::channel(1024);
+        LineSource::forward(stdin(), max_line_bytes, sender);
+        LineSource { name: "stdin".to_string(), lines }
+    }
+
+    pub async fn tcp(listen: &str, max_line_bytes: usize) -> Result<LineSource, SourceError> {
+        let listener = TcpListener::bind(listen).await?;
+        info!("Reading messages from connections to {}", listen);
+        let (sender, lines) = mpsc::channel(1024);
+        tokio::spawn(async move {
+            loop {
+                match listener.accept().await {
+                    Ok((socket, _)) => LineSource::forward(socket, max_line_bytes, sender.clone()),
+                    Err(e) => warn!("Failed to accept a stream connection: {}", e),
+                }
+            }
+        });
+        Ok(LineSource { name: format!("tcp://{}", listen), lines })
+    }
+
+    /// Replaces a socket file left behind by an earlier run.
+    #[cfg(unix)]
+    pub async fn unix(path: &std::path::Path, max_line_bytes: usize) -> Result<LineSource, SourceError> {
+        let _ = std::fs::remove_file(path);
+        let listener = tokio::net::UnixListener::bind(path)?;
+        info!("Reading messages from connections to {}", path.display());
+        let (sender, lines) = mpsc::channel(1024);
+        tokio::spawn(async move {
+            loop {
+                match listener.accept().await {
+                    Ok((socket, _)) => LineSource::forward(socket, max_line_bytes, sender.clone()),
+                    Err(e) => warn!("Failed to accept a stream connection: {}", e),
+                }
+            }
+        });
+        Ok(LineSource { name: format!("unix://{}", path.display()), lines })
+    }
+}
+
+#[async_trait]
+impl StreamSource for LineSource {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    async fn next(&mut self) -> Result<Option<Result<Message, LineTooLong>>, SourceError> {
+        Ok(self.lines.recv().await.map(|line| line.map(Message::from_line)))
+    }
+}
+
+/// Follows a file as it grows. Starts over from the top when the file is
+/// truncated or replaced by a shorter one, as log rotation does.
+pub struct TailSource {
+    name: String,
+    path: PathBuf,
+    /// Keeps a line whose newline hasn't been written yet.
+    lines: BoundedLines<BufReader<File>>,
+    /// Where `lines` started reading.
+    start: u64,
+    max_line_bytes: usize,
+    poll_interval: Duration,
+}
+
+impl TailSource {
+    pub async fn open(path: impl Into<PathBuf>, from_start: bool, poll_interval: Duration, max_line_bytes: usize) -> Result<TailSource, SourceError> {
+        let path = path.into();
+        let mut file = File::open(&path).await?;
+        let start = if from_start { 0 } else { file.seek(SeekFrom::End(0)).await? };
+        Ok(TailSource {
+            name: format!("file://{}", path.display()),
+            path,
+            lines: BoundedLines::new(BufReader::new(file), max_line_bytes),
+            start,
+            max_line_bytes,
+            poll_interval,
+        })
+    }
+
+    async fn reopen(&mut self) -> Result<(), SourceError> {
+        info!("{} was truncated or rotated, reading it from the start", self.path.display());
+        self.lines = BoundedLines::new(BufReader::new(File::open(&self.path).await?), self.max_line_bytes);
+        self.start = 0;
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl StreamSource for TailSource {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    async fn next(&mut self) -> Result<Option<Result<Message, LineTooLong>>, SourceError> {
+        loop {
+            match self.lines.next_complete_line().await? {
+                Some(Ok(line)) if line.trim().is_empty() => continue,
+                Some(line) => return Ok(Some(line.map(Message::from_line))),
+                None => {}
+            }
+            tokio::time::sleep(self.poll_interval).await;
+            match tokio::fs::metadata(&self.path).await {
+                Ok(metadata) if metadata.len() < self.start + self.lines.consumed() => self.reopen().await?,
+                // Mid-rotation; the new file shows up on a later poll.
+                Ok(_) | Err(_) => {}
+            }
+        }
+    }
+}
+
+/// Consumes a topic as part of a consumer group, committing offsets
+/// automatically. Works with Kafka and compatible brokers such as Redpanda.
+#[cfg(feature = "kafka")]
+pub struct KafkaSource {
+    name: String,
+    consumer: rdkafka::consumer::StreamConsumer,
+    max_line_bytes: usize,
+}
+
+#[cfg(feature = "kafka")]
+impl KafkaSource {
+    pub fn new(brokers: &str, topic: &str, group: &str, max_line_bytes: usize) -> Result<KafkaSource, SourceError> {
+        use rdkafka::consumer::Consumer;
+
+        let consumer: rdkafka::consumer::StreamConsumer = rdkafka::ClientConfig::new()
+            .set("bootstrap.servers", brokers)
+            .set("group.id", group)
+            .set("enable.auto.commit", "true")
+            .set("auto.offset.reset", "earliest")
+            .create()?;
+        consumer.subscribe(&[topic])?;
+        info!("Consuming {} from {} as group {}", topic, brokers, group);
+        Ok(KafkaSource {
+            name: format!("kafka://{}/{}", brokers, topic),
+            consumer,
+            max_line_bytes,
+        })
+    }
+}
+
+#[cfg(feature = "kafka")]
+#[async_trait]
+impl StreamSource for KafkaSource {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Records without a request id get `topic/partition/offset`.
+    async fn next(&mut self) -> Result<Option<Result<Message, LineTooLong>>, SourceError> {
+        use rdkafka::message::Message as _;
+
+        loop {
+            let record = self.consumer.recv().await?;
+            match record.payload_view::<str>() {
+                Some(Ok(payload)) if payload.len() > self.max_line_bytes => return Ok(Some(Err(LineTooLong { limit: self.max_line_bytes }))),
+                Some(Ok(payload)) if !payload.trim().is_empty() => {
+                    let mut message = Message::from_line(payload.to_string());
+                    if message.request_id.is_none() {
+                        message.request_id = Some(format!("{}/{}/{}", record.topic(), record.partition(), record.offset()));
+                    }
+                    return Ok(Some(Ok(message)));
+                }
+                Some(Err(e)) => warn!("Skipping record {}/{}/{}: {}", record.topic(), record.partition(), record.offset(), e),
+                _ => {}
+            }
+        }
+    }
+}
+
+/// Opens `stream.source`, reading messages of at most `max_line_bytes`.
+pub async fn open_source(config: &StreamConfig, max_line_bytes: usize) -> Result<Box<dyn StreamSource>, SourceError> {
+    let source: Box<dyn StreamSource> = match SourceSpec::parse(&config.source)? {
+        SourceSpec::Stdin => Box::new(LineSource::stdin(max_line_bytes)),
+        SourceSpec::Tcp(listen) => Box::new(LineSource::tcp(&listen, max_line_bytes).await?),
+        #[cfg(unix)]
+        SourceSpec::Unix(path) => Box::new(LineSource::unix(&path, max_line_bytes).await?),
+        #[cfg(not(unix))]
+        SourceSpec::Unix(_) => return Err("unix sockets are not supported on this platform".into()),
+        SourceSpec::File(path) => {
+            let poll_interval = Duration::from_millis(config.poll_interval_ms);
+            Box::new(TailSource::open(path, config.tail_from_start, poll_interval, max_line_bytes).await?)
+        }
+        #[cfg(feature = "kafka")]
+        SourceSpec::Kafka { brokers, topic } => Box::new(KafkaSource::new(&brokers, &topic, &config.kafka_group, max_line_bytes)?),
+        #[cfg(not(feature = "kafka"))]
+        SourceSpec::Kafka { .. } => return Err("kafka sources need a build with the kafka feature".into()),
+    };
+    Ok(source)
+}
+
+/// Opens `stream.sink`: `stdout`, `file:///path` (appended to) or
+/// `tcp://host:port` (connected to).
+pub async fn open_sink(value: &str) -> Result<Box<dyn AsyncWrite + Send + Unpin>, SourceError> {
+    if value == "stdout" || value == "-" {
+        return Ok(Box::new(stdout()));
+    }
+    match value.split_once("://") {
+        Some(("file", path)) if !path.is_empty() => Ok(Box::new(OpenOptions::new().create(true).append(true).open(path).await?)),
+        Some(("tcp", address)) if !address.is_empty() => Ok(Box::new(TcpStream::connect(address).await?)),
+        _ => Err(format!("'{}' must be stdout, file:///path or tcp://host:port", value).into()),
+    }
+}
@@ -0,0 +1,294 @@
+use clap::Args;
+use log::{info, warn};
+use serde::Serialize;
+use std::error::Error;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::io::AsyncWriteExt;
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+use tracing::{info_span, Instrument};
+use tracing_opentelemetry::OpenTelemetrySpanExt;
+use uuid::Uuid;
+
+use crate::alerts::{self, AlertHandle};
+use crate::cli::DetectionArgs;
+use crate::config::Config;
+use crate::conversation::Conversations;
+use crate::db::audit::{AuditWriter, ValidationRecord};
+use crate::db::store::PatternStore;
+use crate::db::Database;
+use crate::documents::{self, DocumentBatch};
+use crate::findings::Direction;
+use crate::guard::Verdict;
+use crate::logging;
+use crate::metrics;
+use crate::pool::{PoolHandle, WorkerPool};
+use crate::reputation::{Escalation, ReputationTracker};
+use crate::serve::shutdown_signal;
+use crate::sources::{self, LineTooLong, Message};
+use crate::telemetry;
+
+pub fn parse_direction(value: &str) -> Result<Direction, String> {
+    Direction::parse(value).ok_or_else(|| format!("expected input or output, got '{}'", value))
+}
+
+#[derive(Debug, Args)]
+pub struct StreamArgs {
+    /// Whether messages that don't say otherwise are prompts (input) or model
+    /// responses (output).
+    #[arg(long, env = "LLM_VALIDATOR_DIRECTION", default_value = "input", value_parser = parse_direction)]
+    pub direction: Direction,
+
+    /// stdin, tcp://host:port, unix:///path, file:///path or kafka://brokers/topic.
+    #[arg(long)]
+    pub source: Option<String>,
+
+    /// stdout, file:///path or tcp://host:port.
+    #[arg(long)]
+    pub sink: Option<String>,
+
+    #[command(flatten)]
+    pub detection: DetectionArgs,
+}
+
+/// One line written to the stream sink.
+#[derive(Debug, Serialize)]
+struct StreamVerdict<'a> {
+    request_id: &'a str,
+    #[serde(flatten)]
+    verdict: &'a Verdict,
+}
+
+#[derive(Debug, Serialize)]
+struct StreamDocuments<'a> {
+    request_id: &'a str,
+    #[serde(flatten)]
+    batch: &'a DocumentBatch,
+}
+
+#[derive(Debug, Serialize)]
+struct StreamRejection<'a> {
+    request_id: &'a str,
+    error: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    retry_after_secs: Option<u64>,
+}
+
+/// Renders the sink line for a line too long to validate.
+async fn reject(error: LineTooLong) -> Result<String, serde_json::Error> {
+    let request_id = Uuid::new_v4().to_string();
+    warn!(request_id = request_id.as_str(); "Rejected message: {}", error);
+    let rejection = StreamRejection {
+        request_id: &request_id,
+        error: error.to_string(),
+        retry_after_secs: None,
+    };
+    Ok(serde_json::to_string(&rejection)? + "\n")
+}
+
+/// Validates one message on the pool and renders its sink line.
+async fn validate(
+    message: Message,
+    direction: Direction,
+    pool: PoolHandle,
+    audit: Option<AuditWriter>,
+    alerts: Option<AlertHandle>,
+    reputation: Option<Arc<ReputationTracker>>,
+) -> Result<String, serde_json::Error> {
+    let started = Instant::now();
+    let direction = message.direction.unwrap_or(direction);
+    let request_id = message.request_id.clone().unwrap_or_else(|| Uuid::new_v4().to_string());
+    let app = message.app.clone().unwrap_or_default();
+    // Only messages naming their client count towards a reputation; a
+    // stream has no peer address to fall back to.
+    let client = reputation.zip(message.client_id.clone());
+    if let Some(admission) = client.as_ref().map(|(r, id)| r.admit(id)).filter(|a| !a.is_allowed()) {
+        warn!(request_id = request_id.as_str(), client_id = message.client_id.as_deref().unwrap_or(""), level = admission.level.as_str(); "Turned away message");
+        let rejection = StreamRejection {
+            request_id: &request_id,
+            error: admission.message(),
+            retry_after_secs: admission.retry_after.map(|d| d.as_secs().max(1)),
+        };
+        return Ok(serde_json::to_string(&rejection)? + "\n");
+    }
+    let span = info_span!(
+        "validate",
+        pipeline = "stream",
+        request_id = request_id.as_str(),
+        direction = direction.as_str(),
+        app = app.as_str(),
+        session_id = message.session_id.as_deref().unwrap_or("")
+    );
+    span.set_parent(telemetry::extract_context(message.traceparent.as_deref(), message.tracestate.as_deref()));
+    if !message.documents.is_empty() {
+        let alert_texts: Option<Vec<String>> = alerts.as_ref().map(|_| message.documents.iter().map(|d| d.text.clone()).collect());
+        let line = match documents::check_on_pool(&pool, message.documents, message.app.clone()).instrument(span).await {
+            Ok(verdicts) => {
+                let batch = DocumentBatch::new(verdicts);
+                batch.log_blocked(&request_id);
+                if let (Some(alerts), Some(texts)) = (&alerts, &alert_texts) {
+                    for (document, text) in batch.documents.iter().zip(texts) {
+                        alerts.raise(&document.as_verdict(), text, &request_id, message.app.as_deref());
+                    }
+                }
+                if let Some(audit) = &audit {
+                    for document in &batch.documents {
+                        let record = ValidationRecord::new(Direction::Input, document.findings.clone(), started.elapsed());
+                        audit.record(record.for_request(&request_id, message.app.as_deref()));
+                    }
+                }
+                serde_json::to_string(&StreamDocuments { request_id: &request_id, batch: &batch })?
+            }
+            Err(e) => {
+                warn!(request_id = request_id.as_str(), app = app.as_str(); "Could not validate documents: {}", e);
+                if let Some(audit) = &audit {
+                    audit.record(ValidationRecord::failed(Direction::Input, started.elapsed()).for_request(&request_id, message.app.as_deref()));
+                }
+                serde_json::to_string(&StreamRejection {
+                    request_id: &request_id,
+                    error: e.to_string(),
+                    retry_after_secs: None,
+                })?
+            }
+        };
+        return Ok(line + "\n");
+    }
+    let alert_text = alerts.as_ref().map(|_| message.text.clone());
+    let content = logging::content(&message.text);
+
+    let mut line = match pool.submit_turn(message.session_id, message.text, direction, message.app.clone()).instrument(span).await {
+        Ok(verdict) => {
+            metrics::record_verdict("stream", direction, verdict.blocked, started.elapsed());
+            if verdict.blocked {
+                warn!(
+                    request_id = request_id.as_str(),
+                    direction = direction.as_str(),
+                    app = app.as_str(),
+                    findings = verdict.findings.len(),
+                    content = content.as_str();
+                    "Blocked message"
+                );
+                if let (Some(alerts), Some(text)) = (&alerts, &alert_text) {
+                    alerts.raise(&verdict, text, &request_id, message.app.as_deref());
+                }
+            }
+            if let Some(audit) = &audit {
+                let record = ValidationRecord::new(direction, verdict.findings.clone(), started.elapsed());
+                audit.record(record.for_request(&request_id, message.app.as_deref()));
+            }
+            if let Some((reputation, client_id)) = &client {
+                let level = reputation.record(client_id, &verdict.findings);
+                if verdict.blocked && level >= Escalation::Warn {
+                    warn!(request_id = request_id.as_str(), client_id = client_id.as_str(), level = level.as_str(); "Client keeps sending blocked messages");
+                }
+            }
+            serde_json::to_string(&StreamVerdict { request_id: &request_id, verdict: &verdict })?
+        }
+        Err(e) => {
+            warn!(request_id = request_id.as_str(), app = app.as_str(); "Could not validate message: {}", e);
+            if let Some(audit) = &audit {
+                audit.record(ValidationRecord::failed(direction, started.elapsed()).for_request(&request_id, message.app.as_deref()));
+            }
+            serde_json::to_string(&StreamRejection {
+                request_id: &request_id,
+                error: e.to_string(),
+                retry_after_secs: None,
+            })?
+        }
+    };
+    line.push('\n');
+    Ok(line)
+}
+
+/// Handles `stream [--source SPEC] [--sink SPEC] [--direction input|output] [--detectors a,b] [--policy FILE]`.
+///
+/// Reads messages from `stream.source` (plain text or NDJSON lines, as for
+/// `serve`), validates them on the worker pool and writes one JSON verdict
+/// line per message, tagged with its `request_id`, to `stream.sink` in the
+/// order the messages arrived; lines too long for any app's
+/// `max_input_bytes` get an error line instead. Blocked messages raise alerts, and messages
+/// with a `session_id` are checked as conversation turns and messages with
+/// `documents` get a verdict per document, audited and alerted on, like `serve`.
+/// Messages with a `client_id` count towards that client's reputation when
+/// `reputation.enabled` is set, and verdicts go to the audit trail when
+/// `sinks.audit` is enabled. Runs until the source ends, or until SIGTERM
+/// or Ctrl-C.
+pub async fn run_stream_command(args: &StreamArgs, config: &Config, db: Option<&Database>, store: Arc<dyn PatternStore>) -> Result<(), Box<dyn Error>> {
+    let guard = Arc::new(config.detection.guard(store, config.workers.compute_pool()?).await?);
+    let max_line_bytes = sources::max_line_bytes(guard.policy());
+    let conversations = Conversations::from_config(&config.conversations, db)?;
+    let pool = WorkerPool::spawn_with_conversations(guard, conversations, config.workers.pool_config());
+    let (audit, audit_task) = match AuditWriter::from_config(&config.sinks.audit, db) {
+        Some((writer, task)) => (Some(writer), Some(task)),
+        None => (None, None),
+    };
+    let (alerts, alerts_task) = match alerts::start(&config.sinks.alerts).await? {
+        Some((handle, task)) => (Some(handle), Some(task)),
+        None => (None, None),
+    };
+    let (reputation, reputation_task) = match ReputationTracker::from_config(&config.reputation, db).await? {
+        Some((tracker, task)) => (Some(tracker), task),
+        None => (None, None),
+    };
+    let mut source = sources::open_source(&config.stream, max_line_bytes).await?;
+    let mut sink = sources::open_sink(&config.stream.sink).await?;
+    info!("Validating messages from {}", source.name());
+
+    // Verdicts are awaited in arrival order; the bound stops reading once
+    // queue_depth messages are in flight.
+    let (pending, mut verdicts) = mpsc::channel::<JoinHandle<Result<String, serde_json::Error>>>(config.workers.queue_depth);
+    let writer = tokio::spawn(async move {
+        while let Some(task) = verdicts.recv().await {
+            match task.await {
+                Ok(Ok(line)) => {
+                    sink.write_all(line.as_bytes()).await?;
+                    sink.flush().await?;
+                }
+                Ok(Err(e)) => warn!("Failed to encode a verdict: {}", e),
+                Err(e) => warn!("Validation task failed: {}", e),
+            }
+        }
+        Ok::<_, std::io::Error>(())
+    });
+
+    let shutdown = shutdown_signal();
+    tokio::pin!(shutdown);
+    loop {
+        let message = tokio::select! {
+            next = source.next() => match next? {
+                Some(message) => message,
+                None => break,
+            },
+            result = &mut shutdown => {
+                result?;
+                break;
+            }
+        };
+        let task = match message {
+            Ok(message) => tokio::spawn(validate(message, args.direction, pool.handle(), audit.clone(), alerts.clone(), reputation.clone())),
+            Err(e) => tokio::spawn(reject(e)),
+        };
+        if pending.send(task).await.is_err() {
+            break;
+        }
+    }
+
+    drop(pending);
+    writer.await??;
+    pool.shutdown(Duration::from_secs(config.server.shutdown_timeout_secs)).await;
+    if let (Some(reputation), Some(task), Some(db)) = (&reputation, reputation_task, db) {
+        task.abort();
+        if let Err(e) = reputation.flush(db).await {
+            warn!("Failed to save client reputations: {}", e);
+        }
+    }
+    if let (Some(audit), Some(task)) = (audit, audit_task) {
+        audit.finish(task).await;
+    }
+    drop(alerts);
+    if let Some(task) = alerts_task {
+        let _ = task.await;
+    }
+    Ok(())
+}
@@ -0,0 +1,62 @@
+use log::info;
+use opentelemetry::propagation::TextMapPropagator;
+use opentelemetry::{global, Context, KeyValue};
+use opentelemetry_otlp::WithExportConfig;
+use opentelemetry_sdk::propagation::TraceContextPropagator;
+use opentelemetry_sdk::trace::{self as sdktrace, Sampler};
+use opentelemetry_sdk::{runtime, Resource};
+use std::collections::HashMap;
+use std::error::Error;
+use tracing_subscriber::layer::SubscriberExt;
+
+use crate::config::TracingConfig;
+
+/// Flushes spans still buffered by the batch exporter when dropped. Keep it
+/// alive until the command finishes.
+pub struct TelemetryGuard;
+
+impl Drop for TelemetryGuard {
+    fn drop(&mut self) {
+        global::shutdown_tracer_provider();
+    }
+}
+
+/// Starts exporting spans over OTLP/gRPC when `tracing.otlp_endpoint` is
+/// set. Without an endpoint spans are never recorded and cost next to
+/// nothing.
+pub fn init(config: &TracingConfig) -> Result<Option<TelemetryGuard>, Box<dyn Error>> {
+    let endpoint = match &config.otlp_endpoint {
+        Some(endpoint) => endpoint,
+        None => return Ok(None),
+    };
+
+    global::set_text_map_propagator(TraceContextPropagator::new());
+    let tracer = opentelemetry_otlp::new_pipeline()
+        .tracing()
+        .with_exporter(opentelemetry_otlp::new_exporter().tonic().with_endpoint(endpoint.clone()))
+        .with_trace_config(
+            sdktrace::config()
+                .with_sampler(Sampler::ParentBased(Box::new(Sampler::TraceIdRatioBased(config.sample_ratio))))
+                .with_resource(Resource::new(vec![KeyValue::new("service.name", config.service_name.clone())])),
+        )
+        .install_batch(runtime::Tokio)?;
+
+    let subscriber = tracing_subscriber::registry().with(tracing_opentelemetry::layer().with_tracer(tracer));
+    tracing::subscriber::set_global_default(subscriber)?;
+    info!("Exporting traces to {}", endpoint);
+    Ok(Some(TelemetryGuard))
+}
+
+/// The caller's trace context from W3C `traceparent` and `tracestate`
+/// values, as forwarded by a proxy or API gateway. Empty when there are none
+/// or they don't parse, so the message starts a new trace.
+pub fn extract_context(traceparent: Option<&str>, tracestate: Option<&str>) -> Context {
+    let mut carrier = HashMap::new();
+    if let Some(value) = traceparent {
+        carrier.insert("traceparent".to_string(), value.to_string());
+    }
+    if let Some(value) = tracestate {
+        carrier.insert("tracestate".to_string(), value.to_string());
+    }
+    TraceContextPropagator::new().extract(&carrier)
+}
@@ -0,0 +1,191 @@
+use clap::Args;
+use serde::Serialize;
+use std::error::Error;
+
+use crate::datasets::{load_dataset, split_for, Split};
+use crate::config::Config;
+use crate::db::experiments::ConfusionMatrix;
+use crate::db::store::PatternStore;
+use crate::db::Database;
+use crate::detectors::Detector;
+use crate::eval::LabeledExample;
+use crate::policy::Policy;
+
+/// Outcome of flagging every example scoring at or above `threshold`.
+#[derive(Debug, Clone, Copy, Serialize)]
+pub struct CurvePoint {
+    pub threshold: f32,
+    pub true_positive_rate: f32,
+    pub false_positive_rate: f32,
+    pub precision: f32,
+    pub recall: f32,
+}
+
+/// ROC and PR curves for one detector over a labeled dataset.
+#[derive(Debug, Clone, Serialize)]
+pub struct DetectorSweep {
+    pub detector: String,
+    pub positives: u32,
+    pub negatives: u32,
+    /// One point per distinct score, from the highest threshold to the lowest.
+    pub points: Vec<CurvePoint>,
+    pub roc_auc: f32,
+    pub average_precision: f32,
+}
+
+impl DetectorSweep {
+    /// The threshold with the highest true positive rate whose false positive
+    /// rate stays within `target_fpr`. Ties go to the higher threshold.
+    pub fn best_operating_point(&self, target_fpr: f32) -> Option<CurvePoint> {
+        let mut best: Option<CurvePoint> = None;
+        for point in self.points.iter().filter(|p| p.false_positive_rate <= target_fpr) {
+            if best.map_or(true, |b| point.true_positive_rate > b.true_positive_rate) {
+                best = Some(*point);
+            }
+        }
+        best
+    }
+}
+
+/// Scores every applicable example once and sweeps the threshold over each
+/// distinct score. Examples in a direction the detector ignores are skipped.
+pub fn sweep(detector: &dyn Detector, examples: &[LabeledExample]) -> DetectorSweep {
+    let mut scored: Vec<(f32, bool)> = examples
+        .iter()
+        .filter(|e| detector.applies_to(e.direction))
+        .map(|e| (detector.score(&e.text, e.direction), e.is_malicious()))
+        .collect();
+    scored.sort_by(|a, b| b.0.total_cmp(&a.0));
+
+    let positives = scored.iter().filter(|(_, malicious)| *malicious).count() as u32;
+    let negatives = scored.len() as u32 - positives;
+    let ratio = |num: u32, den: u32| if den == 0 { 0.0 } else { num as f32 / den as f32 };
+
+    let mut matrix = ConfusionMatrix {
+        true_negatives: negatives,
+        false_negatives: positives,
+        ..ConfusionMatrix::default()
+    };
+    let mut points = Vec::new();
+    let mut i = 0;
+    while i < scored.len() {
+        let threshold = scored[i].0;
+        while i < scored.len() && scored[i].0 == threshold {
+            if scored[i].1 {
+                matrix.true_positives += 1;
+                matrix.false_negatives -= 1;
+            } else {
+                matrix.false_positives += 1;
+                matrix.true_negatives -= 1;
+            }
+            i += 1;
+        }
+        let metrics = matrix.metrics();
+        points.push(CurvePoint {
+            threshold,
+            true_positive_rate: ratio(matrix.true_positives, positives),
+            false_positive_rate: ratio(matrix.false_positives, negatives),
+            precision: metrics.precision,
+            recall: metrics.recall,
+        });
+    }
+
+    let mut roc_auc = 0.0;
+    let mut average_precision = 0.0;
+    let (mut last_fpr, mut last_tpr, mut last_recall) = (0.0, 0.0, 0.0);
+    for point in &points {
+        roc_auc += (point.false_positive_rate - last_fpr) * (point.true_positive_rate + last_tpr) / 2.0;
+        average_precision += (point.recall - last_recall) * point.precision;
+        last_fpr = point.false_positive_rate;
+        last_tpr = point.true_positive_rate;
+        last_recall = point.recall;
+    }
+
+    DetectorSweep {
+        detector: detector.name().to_string(),
+        positives,
+        negatives,
+        points,
+        roc_auc,
+        average_precision,
+    }
+}
+
+#[derive(Debug, Args)]
+pub struct TuneArgs {
+    pub dataset_id: i32,
+
+    /// Highest false positive rate an operating point may have.
+    #[arg(long, default_value_t = 0.01)]
+    pub target_fpr: f32,
+
+    /// Comma separated detectors to sweep.
+    #[arg(long)]
+    pub detectors: Option<String>,
+
+    /// Policy file to write the chosen thresholds into; defaults to the
+    /// configured policy.
+    #[arg(long)]
+    pub policy: Option<String>,
+
+    /// Where to save the full ROC/PR curves as JSON.
+    #[arg(long)]
+    pub curves: Option<String>,
+}
+
+/// Handles `tune <dataset_id> [--target-fpr F] [--detectors a,b] [--policy FILE] [--curves FILE]`.
+///
+/// Sweeps every detector over the train and validation splits (the test
+/// split stays held out for `eval`), prints the best threshold for the target
+/// false positive rate and, with `--policy`, writes those thresholds into the
+/// policy file. `--curves` saves the full ROC/PR curves as JSON.
+pub async fn run_tune_command(args: &TuneArgs, config: &Config, db: &Database, store: &dyn PatternStore) -> Result<(), Box<dyn Error>> {
+    let dataset_id = args.dataset_id;
+    let target_fpr = args.target_fpr;
+    let policy_path = args.policy.as_ref().or(config.detection.policy.as_ref());
+    let curves_path = &args.curves;
+
+    let dataset = db.fetch_dataset(dataset_id).await?;
+    let examples: Vec<LabeledExample> = load_dataset(&dataset)?
+        .into_iter()
+        .filter(|e| split_for(&e.text) != Split::Test)
+        .collect();
+    let detectors = config.detection.detector_config().build(store).await?;
+
+    let mut policy = match policy_path {
+        Some(path) => Some(Policy::load_or_default(path)?),
+        None => None,
+    };
+    let mut sweeps = Vec::new();
+    for detector in &detectors {
+        let sweep = sweep(detector.as_ref(), &examples);
+        println!(
+            "{}: {} positives, {} negatives, ROC AUC {:.4}, average precision {:.4}",
+            sweep.detector, sweep.positives, sweep.negatives, sweep.roc_auc, sweep.average_precision
+        );
+        match sweep.best_operating_point(target_fpr) {
+            Some(point) => {
+                println!(
+                    "  threshold {:.4}: TPR {:.4}, FPR {:.4}, precision {:.4}",
+                    point.threshold, point.true_positive_rate, point.false_positive_rate, point.precision
+                );
+                if let Some(policy) = policy.as_mut() {
+                    policy.thresholds.insert(sweep.detector.clone(), point.threshold);
+                }
+            }
+            None => println!("  no threshold keeps FPR at or below {}", target_fpr),
+        }
+        sweeps.push(sweep);
+    }
+
+    if let (Some(policy), Some(path)) = (policy, policy_path) {
+        policy.save(path)?;
+        println!("Wrote thresholds to {}", path);
+    }
+    if let Some(path) = curves_path {
+        std::fs::write(&path, serde_json::to_string_pretty(&sweeps)?)?;
+        println!("Wrote curves to {}", path);
+    }
+
+    Ok(())
+}